@@ -0,0 +1,90 @@
+use crate::board::Board;
+use crate::engine::Engine;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_BENCH_DEPTH: u32 = 4;
+
+/// A mix of openings, middlegames and endgames. Changing this list changes
+/// the node signature.
+const BENCH_POSITIONS: [&str; 10] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w",
+    "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w",
+    "r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP3PPP/R2QKB1R w",
+    "r2q1rk1/1b1nbppp/p2ppn2/1p6/3NP3/1BN1B3/PPP1QPPP/R4RK1 b",
+    "2r2rk1/pp1bqppp/2n1pn2/3p4/3P4/2PBPN2/P1Q2PPP/R4RK1 w",
+    "r4rk1/pp3ppp/2n1b3/3p4/3P4/2N1B3/PP3PPP/R4RK1 b",
+    "8/5pk1/6p1/3R4/7P/6P1/r4P2/6K1 w",
+    "8/8/4k3/3p4/3P4/4K3/8/8 w",
+    "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w",
+    "4r1k1/5ppp/8/8/8/8/1Q3PPP/6K1 b",
+];
+
+pub struct BenchResult {
+    pub nodes: u64,
+    pub time: Duration,
+}
+
+impl BenchResult {
+    pub fn nps(&self) -> u64 {
+        (self.nodes as f64 / self.time.as_secs_f64().max(1e-9)) as u64
+    }
+}
+
+/// Searches every bench position to `depth` from an empty hash table, on a
+/// single thread so the node count is a signature of the search: any change
+/// to search or evaluation behaviour changes it. Runs on a fresh engine
+/// with the evaluator of `source`, leaving its game untouched.
+pub fn bench(source: &Engine, depth: u32) -> BenchResult {
+    let mut engine = Engine::new();
+    engine.set_evaluator(source.evaluator().clone_box());
+
+    let mut nodes = 0;
+    let start = Instant::now();
+
+    for fen in BENCH_POSITIONS {
+        engine.set_position(Board::from_fen(fen).unwrap());
+        engine.clear_hash();
+        engine.search(depth);
+
+        nodes += engine.nodes();
+    }
+
+    let time = start.elapsed();
+
+    BenchResult { nodes, time }
+}
+
+/// Handles `bench [depth]`.
+pub fn run_bench(engine: &Engine, input: &str) {
+    let depth = input
+        .split_whitespace()
+        .nth(1)
+        .and_then(|d| d.parse().ok())
+        .unwrap_or(DEFAULT_BENCH_DEPTH);
+
+    print_bench(engine, depth);
+}
+
+/// Runs `bench` and prints its result.
+pub fn print_bench(engine: &Engine, depth: u32) {
+    let result = bench(engine, depth);
+
+    println!("Depth: {}", depth);
+    println!("Nodes searched: {}", result.nodes);
+    println!("Time: {} ms", result.time.as_millis());
+    println!("Nodes/second: {}", result.nps());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bench_is_deterministic() {
+        let first = bench(&Engine::new(), 2);
+        let second = bench(&Engine::new(), 2);
+
+        assert!(first.nodes > 0);
+        assert_eq!(first.nodes, second.nodes);
+    }
+}
@@ -9,6 +9,12 @@ pub struct BitBoard {
     board: u64,
 }
 
+impl Default for BitBoard {
+    fn default() -> Self {
+        BitBoard::new()
+    }
+}
+
 impl BitBoard {
     pub fn new() -> BitBoard {
         BitBoard { board: 0 }
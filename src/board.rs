@@ -1,13 +1,27 @@
-use crate::bitboard::BitBoard;
+use crate::bitboard::{BitBoard, EMPTY};
+use crate::cache::bishop::get_bishop_moves;
+use crate::cache::king::KING_MOVES;
+use crate::cache::knight::KNIGHT_MOVES;
+use crate::cache::pawn::PAWN_ATTACKS;
+use crate::cache::rook::get_rook_moves;
 use crate::coord::Coord;
+use crate::evaluate::{taper, PIECE_VALUES};
+use crate::king_safety::evaluate_king_safety;
+use crate::mobility::evaluate_mobility;
 use crate::moves::{
     filter_check_moves, generate_bishop_moves, generate_king_moves, generate_knights_moves,
     generate_pawn_moves, generate_queen_moves, generate_rook_moves,
 };
+use crate::nnue::{Network, NnueState};
+use crate::params::{EvalParams, DEFAULT_PARAMS};
+use crate::pawns::{evaluate_pawns, PawnHashTable};
 use crate::piece::{Piece, PieceEnum};
 use crate::r#move::Move;
+use crate::trace::EvalTrace;
+use crate::zobrist::ZOBRIST;
 use colored::Colorize;
 use std::fmt::Display;
+use std::sync::Arc;
 
 #[derive(Clone, Copy, PartialEq)]
 pub struct CheckState {
@@ -25,14 +39,26 @@ pub struct MoveWithCapture {
 
 #[derive(Clone)]
 pub struct Board {
-    pub white: OneSideBoard,
-    pub black: OneSideBoard,
+    white: OneSideBoard,
+    black: OneSideBoard,
     pub turn: bool,
     past_moves: Vec<MoveWithCapture>,
-
-    pub check_states: [CheckState; 2],
+    pieces_hash: u64,
+    pawn_hash: u64,
+    // Material and piece-square totals, white minus black, kept up to date
+    // by `set_piece` and `unset_piece` with `params`, the defaults if None
+    mg_score: i16,
+    eg_score: i16,
+    phase: i16,
+    params: Option<Arc<EvalParams>>,
+    // Network accumulators, only kept when searching with the NNUE
+    nnue: Option<NnueState>,
+
+    check_states: [CheckState; 2],
 }
 
+/// The pieces of one color, one bitboard per kind of piece. Only `Board`
+/// changes them, keeping its hashes and scores in sync.
 #[derive(Clone)]
 pub struct OneSideBoard {
     pieces: [BitBoard; 6],
@@ -40,15 +66,30 @@ pub struct OneSideBoard {
     color: bool,
 }
 
+impl Default for Board {
+    fn default() -> Self {
+        Board::new()
+    }
+}
+
 impl Board {
     pub const STARTING_FEN: &'static str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w";
 
+    /// An empty board, white to move. See `new_game` for the starting
+    /// position.
     pub fn new() -> Board {
         Board {
             white: OneSideBoard::new(true),
             black: OneSideBoard::new(false),
             turn: true,
             past_moves: Vec::new(),
+            pieces_hash: 0,
+            pawn_hash: 0,
+            mg_score: 0,
+            eg_score: 0,
+            phase: 0,
+            params: None,
+            nnue: None,
             check_states: [
                 CheckState {
                     is_check: false,
@@ -70,6 +111,15 @@ impl Board {
         Board::from_fen(Board::STARTING_FEN).unwrap()
     }
 
+    /// The pieces of `color`, true for white.
+    pub fn side(&self, color: bool) -> &OneSideBoard {
+        if color {
+            &self.white
+        } else {
+            &self.black
+        }
+    }
+
     pub fn get_current_player(&self) -> &OneSideBoard {
         if self.turn {
             &self.white
@@ -89,7 +139,7 @@ impl Board {
             PieceEnum::King => generate_king_moves(self, coord, piece.is_white()),
         };
 
-        if piece.is_white() == self.turn && self.check_states[self.turn as usize].is_check {
+        if piece.is_white() == self.turn {
             filter_check_moves(self, moves)
         } else {
             moves
@@ -117,6 +167,24 @@ impl Board {
     }
 
     pub fn make_move(&mut self, m: Move) {
+        if let Some(nnue) = &mut self.nnue {
+            nnue.push();
+        }
+
+        let captured_piece = self.move_piece(m);
+
+        self.past_moves.push(MoveWithCapture {
+            m,
+            captured: captured_piece,
+        });
+        self.turn = !self.turn;
+
+        self.update_flags();
+    }
+
+    /// Moves the piece on `m.from` to `m.to` without touching the turn, the
+    /// move history or the check flags, returning the captured piece if any.
+    fn move_piece(&mut self, m: Move) -> Option<Piece> {
         let src_piece = self.get_piece(m.from).unwrap();
         let captured_piece = self.get_piece(m.to);
 
@@ -128,19 +196,193 @@ impl Board {
 
         self.set_piece(m.to, src_piece);
 
-        self.past_moves.push(MoveWithCapture {
-            m,
-            captured: captured_piece,
-        });
-        self.turn = !self.turn;
+        captured_piece
+    }
 
-        self.update_flags();
+    /// Resolves a move in UCI coordinate notation, such as `e2e4` or
+    /// `e7e8q`, against the legal moves.
+    pub fn parse_uci(&self, text: &str) -> Result<Move, String> {
+        let squares = match text.len() {
+            4 => text.get(0..4),
+            5 if text.ends_with('q') => text.get(0..4),
+            _ => None,
+        }
+        .filter(|_| text.is_ascii());
+
+        let (from, to) = squares
+            .and_then(|squares| {
+                Some((
+                    Coord::from_str(&squares[0..2])?,
+                    Coord::from_str(&squares[2..4])?,
+                ))
+            })
+            .ok_or_else(|| format!("invalid move: {}", text))?;
+
+        self.get_all_moves()
+            .into_iter()
+            .find(|m| m.from == from && m.to == to)
+            .ok_or_else(|| format!("illegal move: {}", text))
+    }
+
+    /// Returns true if playing `m` does not leave the mover's king attacked.
+    pub fn is_legal(&self, m: Move) -> bool {
+        let color = match self.get_piece(m.from) {
+            Some(piece) => piece.is_white(),
+            None => return false,
+        };
+
+        let mut board = Board {
+            white: self.white.clone(),
+            black: self.black.clone(),
+            turn: self.turn,
+            past_moves: Vec::new(),
+            pieces_hash: self.pieces_hash,
+            pawn_hash: self.pawn_hash,
+            mg_score: self.mg_score,
+            eg_score: self.eg_score,
+            phase: self.phase,
+            params: None,
+            nnue: None,
+            check_states: self.check_states,
+        };
+        board.move_piece(m);
+
+        match board.get_piece_index_coord(5, color) {
+            Some(king) => !board.is_square_attacked(king, !color),
+            None => true,
+        }
+    }
+
+    /// Returns true if any piece of color `by` attacks `coord`.
+    pub fn is_square_attacked(&self, coord: Coord, by: bool) -> bool {
+        let attacker = if by { &self.white } else { &self.black };
+        let index = coord.to_index() as usize;
+        let square = BitBoard::from_coord(coord);
+        let occupied = self.occupied_bitboard();
+
+        let bishops = attacker.pieces[2] | attacker.pieces[4];
+        let rooks = attacker.pieces[3] | attacker.pieces[4];
+
+        PAWN_ATTACKS[!by as usize][index] & attacker.pieces[0] != EMPTY
+            || KNIGHT_MOVES[index] & attacker.pieces[1] != EMPTY
+            || KING_MOVES[index] & attacker.pieces[5] != EMPTY
+            || get_bishop_moves(square, occupied) & bishops != EMPTY
+            || get_rook_moves(square, occupied) & rooks != EMPTY
+    }
+
+    /// Returns every piece of either color attacking `coord`, given the
+    /// `occupied` squares. Sliders behind a removed piece show up as soon as
+    /// it is taken out of `occupied`, which gives the x-ray attackers.
+    pub fn attackers_to(&self, coord: Coord, occupied: BitBoard) -> BitBoard {
+        let index = coord.to_index() as usize;
+        let square = BitBoard::from_coord(coord);
+
+        let pawns = (PAWN_ATTACKS[0][index] & self.white.pieces[0])
+            | (PAWN_ATTACKS[1][index] & self.black.pieces[0]);
+        let knights = KNIGHT_MOVES[index] & (self.white.pieces[1] | self.black.pieces[1]);
+        let kings = KING_MOVES[index] & (self.white.pieces[5] | self.black.pieces[5]);
+        let bishops = get_bishop_moves(square, occupied)
+            & (self.white.pieces[2]
+                | self.white.pieces[4]
+                | self.black.pieces[2]
+                | self.black.pieces[4]);
+        let rooks = get_rook_moves(square, occupied)
+            & (self.white.pieces[3]
+                | self.white.pieces[4]
+                | self.black.pieces[3]
+                | self.black.pieces[4]);
+
+        (pawns | knights | kings | bishops | rooks) & occupied
+    }
+
+    /// Static exchange evaluation: the material balance, from the mover's
+    /// point of view, of the capture sequence on `m.to` when both sides
+    /// always recapture with their least valuable attacker. Pawns never
+    /// promote, as in `make_move`.
+    ///
+    /// The search uses it to order captures and to prune losing ones in
+    /// quiescence. The evaluation does not score hanging pieces with it,
+    /// quiescence resolving them instead.
+    pub fn see(&self, m: Move) -> i16 {
+        let mut piece = match self.get_piece(m.from) {
+            Some(piece) => piece,
+            None => return 0,
+        };
+
+        let mut gain = [0i16; 32];
+        gain[0] = self
+            .get_piece(m.to)
+            .map_or(0, |captured| PIECE_VALUES[captured.to_index() as usize]);
+
+        let mut occupied = self.occupied_bitboard();
+        occupied.unset(m.from);
+        let mut attackers = self.attackers_to(m.to, occupied);
+        let mut side = !piece.is_white();
+        let mut depth = 0;
+
+        loop {
+            let side_attackers = attackers & self.get_piece_bitboard(side);
+            if side_attackers == EMPTY {
+                break;
+            }
+
+            // Least valuable attacker of the side to recapture
+            let index = (0..6u8)
+                .find(|&index| side_attackers & self.get_piece_index_bitboard(index, side) != EMPTY)
+                .unwrap();
+
+            // The king may only recapture when nothing defends the square
+            if index == 5 && attackers & self.get_piece_bitboard(!side) != EMPTY {
+                break;
+            }
+
+            depth += 1;
+            gain[depth] = PIECE_VALUES[piece.to_index() as usize] - gain[depth - 1];
+
+            let from = side_attackers & self.get_piece_index_bitboard(index, side);
+            occupied.unset(Coord::from_index(from.trailing_zeros()));
+            attackers = self.attackers_to(m.to, occupied);
+
+            piece = Piece::from_index(index, side);
+            side = !side;
+
+            if depth == gain.len() - 1 {
+                break;
+            }
+        }
+
+        while depth > 0 {
+            gain[depth - 1] = -(-gain[depth - 1]).max(gain[depth]);
+            depth -= 1;
+        }
+
+        gain[0]
+    }
+
+    /// Returns true if the static exchange on `m` wins at least `threshold`.
+    pub fn see_ge(&self, m: Move, threshold: i16) -> bool {
+        self.see(m) >= threshold
+    }
+
+    /// Moves made since the position was set up, oldest first.
+    pub fn moves_played(&self) -> Vec<Move> {
+        self.past_moves.iter().map(|past| past.m).collect()
+    }
+
+    pub fn last_move(&self) -> Option<&MoveWithCapture> {
+        self.past_moves.last()
     }
 
     pub fn undo_move(&mut self, m: Move) {
         if let Some(move_with_capture) = self.past_moves.pop() {
             assert_eq!(m, move_with_capture.m);
 
+            // The accumulators before the move are still on the stack
+            let mut nnue = self.nnue.take();
+            if let Some(nnue) = &mut nnue {
+                nnue.pop();
+            }
+
             let moved_piece = self.get_piece(m.to).unwrap();
             self.unset_piece(m.to, moved_piece);
             self.set_piece(m.from, moved_piece);
@@ -149,6 +391,7 @@ impl Board {
                 self.set_piece(m.to, captured);
             }
 
+            self.nnue = nnue;
             self.turn = !self.turn;
 
             self.update_flags();
@@ -158,10 +401,11 @@ impl Board {
     }
 
     pub fn update_flags(&mut self) {
+        self.check_states[self.turn as usize].is_check = self.calculate_is_check(self.turn);
+
         let moves = self.get_all_moves();
         let is_empty = moves.is_empty();
 
-        self.check_states[self.turn as usize].is_check = self.calculate_is_check(self.turn);
         self.check_states[self.turn as usize].is_checkmate =
             self.calculate_is_checkmate(self.turn, is_empty);
         self.check_states[self.turn as usize].is_stalemate =
@@ -171,8 +415,7 @@ impl Board {
 
     pub fn calculate_is_check(&self, color: bool) -> bool {
         if let Some(king_coord) = self.get_piece_index_coord(5, color) {
-            let enemy_moves = self.get_all_enemy_moves();
-            enemy_moves.iter().any(|m| m.to == king_coord)
+            self.is_square_attacked(king_coord, !color)
         } else {
             true
         }
@@ -210,22 +453,93 @@ impl Board {
         self.is_checkmate(self.turn) || self.is_stalemate(self.turn)
     }
 
-    pub fn evaluate(&self) -> i16 {
-        let mut score: i16 = 0;
+    /// Static evaluation, positive when white is better, interpolated
+    /// between middlegame and endgame by the game phase.
+    pub fn evaluate_white(&self) -> i16 {
+        let pawns = evaluate_pawns(
+            self.get_piece_index_bitboard(0, true),
+            self.get_piece_index_bitboard(0, false),
+            self.params(),
+        );
+
+        self.evaluate_with_pawns(pawns)
+    }
+
+    /// Same as `evaluate_white`, but positive when the side to move is
+    /// better. This is the score negamax works with.
+    pub fn evaluate_relative(&self) -> i16 {
+        self.relative(self.evaluate_white())
+    }
+
+    /// `evaluate_relative` with the pawn structure looked up in `pawns`.
+    pub(crate) fn evaluate_relative_cached(&self, pawns: &mut PawnHashTable) -> i16 {
+        self.relative(self.evaluate_with_pawns(pawns.probe(self)))
+    }
+
+    fn evaluate_with_pawns(&self, (pawns_mg, pawns_eg): (i16, i16)) -> i16 {
+        let (king_mg, king_eg) = evaluate_king_safety(self, self.params());
+        let (mobility_mg, mobility_eg) = evaluate_mobility(self, self.params());
+
+        taper(
+            self.mg_score + pawns_mg + king_mg + mobility_mg,
+            self.eg_score + pawns_eg + king_eg + mobility_eg,
+            self.phase,
+        )
+    }
+
+    fn relative(&self, score: i16) -> i16 {
+        if self.turn {
+            score
+        } else {
+            -score
+        }
+    }
+
+    /// Per-term breakdown of `evaluate_white`.
+    pub fn trace(&self) -> EvalTrace {
+        EvalTrace::new(self, self.params())
+    }
+
+    /// Evaluation parameters of the running score and the evaluation.
+    pub fn params(&self) -> &EvalParams {
+        self.params.as_deref().unwrap_or(&DEFAULT_PARAMS)
+    }
+
+    /// Scores the board with `params` from now on, or with the defaults
+    /// with `None`, recomputing the running score.
+    pub fn set_params(&mut self, params: Option<Arc<EvalParams>>) {
+        self.params = params;
+        self.mg_score = 0;
+        self.eg_score = 0;
 
         for i in 0..64 {
             let coord = Coord::from_index(i);
 
             if let Some(piece) = self.get_piece(coord) {
-                if piece.is_white() {
-                    score += piece.get_score(coord);
-                } else {
-                    score -= piece.get_score(coord);
-                }
+                let (mg, eg) = self.params().piece_value(&piece, coord);
+                let sign = if piece.is_white() { 1 } else { -1 };
+
+                self.mg_score += sign * mg;
+                self.eg_score += sign * eg;
             }
         }
+    }
 
-        score
+    /// Starts keeping accumulators for `network` up to date as moves are
+    /// made and undone, or stops with `None`.
+    pub fn set_network(&mut self, network: Option<Arc<Network>>) {
+        self.nnue = network.map(|network| NnueState::new(network, self));
+    }
+
+    /// Network evaluation from the incrementally updated accumulators,
+    /// positive when the side to move is better. `None` without a network.
+    pub fn evaluate_nnue(&self) -> Option<i16> {
+        self.nnue.as_ref().map(|nnue| nnue.evaluate(self.turn))
+    }
+
+    /// Remaining non-pawn material, from `MAX_PHASE` at the start down to 0.
+    pub fn game_phase(&self) -> i16 {
+        self.phase
     }
 
     pub fn get_piece(&self, coord: Coord) -> Option<Piece> {
@@ -262,7 +576,25 @@ impl Board {
         }
     }
 
+    /// Zobrist key of the position, including the side to move.
+    pub fn hash(&self) -> u64 {
+        self.pieces_hash ^ ZOBRIST.turn(self.turn)
+    }
+
+    /// Zobrist key of the pawns alone, for the pawn hash table.
+    pub fn pawn_hash(&self) -> u64 {
+        self.pawn_hash
+    }
+
     pub fn set_piece(&mut self, coord: Coord, piece: Piece) {
+        self.pieces_hash ^= ZOBRIST.piece(piece, coord);
+        if piece.is_pawn() {
+            self.pawn_hash ^= ZOBRIST.piece(piece, coord);
+        }
+        self.update_score(coord, piece, true);
+        if let Some(nnue) = &mut self.nnue {
+            nnue.update(piece, coord, true);
+        }
         if piece.is_white() {
             self.white.set_piece(coord, piece);
         } else {
@@ -271,6 +603,14 @@ impl Board {
     }
 
     pub fn unset_piece(&mut self, coord: Coord, piece: Piece) {
+        self.pieces_hash ^= ZOBRIST.piece(piece, coord);
+        if piece.is_pawn() {
+            self.pawn_hash ^= ZOBRIST.piece(piece, coord);
+        }
+        self.update_score(coord, piece, false);
+        if let Some(nnue) = &mut self.nnue {
+            nnue.update(piece, coord, false);
+        }
         if piece.is_white() {
             self.white.unset_piece(coord, piece);
         } else {
@@ -278,6 +618,59 @@ impl Board {
         }
     }
 
+    /// Adds `piece` on `coord` to the running score, or removes it.
+    fn update_score(&mut self, coord: Coord, piece: Piece, added: bool) {
+        let (mut mg, mut eg) = match &self.params {
+            Some(params) => params.piece_value(&piece, coord),
+            None => piece.get_value(coord),
+        };
+        let mut phase = piece.get_phase();
+
+        if !piece.is_white() {
+            mg = -mg;
+            eg = -eg;
+        }
+
+        if !added {
+            mg = -mg;
+            eg = -eg;
+            phase = -phase;
+        }
+
+        self.mg_score += mg;
+        self.eg_score += eg;
+        self.phase += phase;
+    }
+
+    pub fn get_piece_index_bitboard(&self, index: u8, color: bool) -> BitBoard {
+        if color {
+            self.white.pieces[index as usize]
+        } else {
+            self.black.pieces[index as usize]
+        }
+    }
+
+    /// Returns true if no enemy pawn can stop or capture the pawn of `color`
+    /// standing on `coord` on its way to promotion.
+    pub fn is_passed_pawn(&self, coord: Coord, color: bool) -> bool {
+        let enemy_pawns = self.get_piece_index_bitboard(0, !color);
+
+        for y in 0..8u8 {
+            let ahead = if color { y > coord.y } else { y < coord.y };
+            if !ahead {
+                continue;
+            }
+
+            for x in coord.x.saturating_sub(1)..=(coord.x + 1).min(7) {
+                if enemy_pawns.get(Coord::new(x, y)) {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
     pub fn has_piece(&self, coord: Coord) -> bool {
         self.white.has_piece(coord) || self.black.has_piece(coord)
     }
@@ -311,6 +704,20 @@ impl Board {
         self.white = board.white;
         self.black = board.black;
         self.turn = board.turn;
+        self.past_moves.clear();
+        self.pieces_hash = board.pieces_hash;
+        self.pawn_hash = board.pawn_hash;
+        self.mg_score = board.mg_score;
+        self.eg_score = board.eg_score;
+        self.phase = board.phase;
+        self.check_states = board.check_states;
+
+        // The new position was scored with the defaults
+        let params = self.params.take();
+        self.set_params(params);
+
+        let network = self.nnue.as_ref().map(|nnue| nnue.network().clone());
+        self.set_network(network);
     }
 
     pub fn from_fen(fen: &str) -> Option<Board> {
@@ -319,12 +726,12 @@ impl Board {
         let mut rank = 7;
         let mut file = 0;
 
-        if let Some(piece_placement) = parts.get(0) {
+        if let Some(piece_placement) = parts.first() {
             for c in piece_placement.chars() {
                 if c == '/' {
                     rank -= 1;
                     file = 0;
-                } else if c.is_digit(10) {
+                } else if c.is_ascii_digit() {
                     file += c.to_digit(10)? as u8;
                 } else {
                     let piece = Piece::from_char(c)?;
@@ -336,16 +743,15 @@ impl Board {
         }
 
         if let Some(turn) = parts.get(1) {
-            board.turn = if *turn == "w" { true } else { false };
+            board.turn = *turn == "w";
         }
 
+        board.update_flags();
+
         Some(board)
     }
 
     pub fn to_fen(&self) -> String {
-        let is_checkmate = self.is_checkmate(self.turn);
-        let is_stalemate = self.is_stalemate(self.turn);
-
         let mut fen = String::with_capacity(50);
         for rank in (0..8).rev() {
             let mut empty = 0;
@@ -376,33 +782,66 @@ impl Board {
     }
 }
 
-impl Display for Board {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl Board {
+    /// The board as text, with file and rank labels, seen from black's side
+    /// when `flipped`. Pieces that can be captured are red and the squares
+    /// of the last move are highlighted.
+    pub fn render(&self, flipped: bool) -> String {
         let mut s = String::new();
         let moves = self.get_all_moves();
         let capturable_coords: Vec<Coord> = moves.into_iter().map(|m| m.to).collect();
-        for rank in (0..8).rev() {
-            for file in 0..8 {
+        let last_move = self.last_move().map(|last| last.m);
+
+        let ranks: Vec<u8> = if flipped {
+            (0..8).collect()
+        } else {
+            (0..8).rev().collect()
+        };
+        let files: Vec<u8> = if flipped {
+            (0..8).rev().collect()
+        } else {
+            (0..8).collect()
+        };
+
+        for &rank in &ranks {
+            s.push_str(&format!("{} ", rank + 1));
+
+            for &file in &files {
                 let coord = Coord::new(file, rank);
-                if let Some(piece) = self.get_piece(coord) {
-                    let piece_char = piece.to_char();
-                    if capturable_coords.contains(&coord) {
-                        s.push_str(&piece_char.to_string().red().bold().to_string());
-                    } else {
-                        let formatted_piece = match piece.is_white() {
-                            true => piece_char.to_string().white().bold().to_string(),
-                            false => piece_char.to_string().black().bold().to_string(),
-                        };
-
-                        s.push_str(&formatted_piece);
+                let square = match self.get_piece(coord) {
+                    Some(piece) => {
+                        let piece_char = piece.to_char().to_string();
+                        if capturable_coords.contains(&coord) {
+                            piece_char.red().bold()
+                        } else if piece.is_white() {
+                            piece_char.white().bold()
+                        } else {
+                            piece_char.black().bold()
+                        }
                     }
+                    None => ".".normal(),
+                };
+
+                if last_move.is_some_and(|m| m.from == coord || m.to == coord) {
+                    s.push_str(&square.on_yellow().to_string());
                 } else {
-                    s.push_str(&".".to_string());
+                    s.push_str(&square.to_string());
                 }
+
+                s.push(' ');
             }
+
+            s.pop();
             s.push('\n');
         }
 
+        s.push(' ');
+        for &file in &files {
+            s.push(' ');
+            s.push((b'a' + file) as char);
+        }
+        s.push('\n');
+
         if self.is_checkmate(self.turn) {
             s.push_str(&"Checkmate!\n".red().bold().to_string());
         }
@@ -410,12 +849,18 @@ impl Display for Board {
             s.push_str(&"Stalemate!\n".yellow().bold().to_string());
         }
 
-        write!(f, "{}", s)
+        s
+    }
+}
+
+impl Display for Board {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.render(false))
     }
 }
 
 impl OneSideBoard {
-    pub fn new(color: bool) -> OneSideBoard {
+    pub(crate) fn new(color: bool) -> OneSideBoard {
         OneSideBoard {
             pieces: [
                 BitBoard::new(),
@@ -458,12 +903,12 @@ impl OneSideBoard {
         }
     }
 
-    pub fn set_piece(&mut self, coord: Coord, piece: Piece) {
+    pub(crate) fn set_piece(&mut self, coord: Coord, piece: Piece) {
         self.pieces[piece.to_index() as usize].set(coord);
         self.all_pieces.set(coord);
     }
 
-    pub fn unset_piece(&mut self, coord: Coord, piece: Piece) {
+    pub(crate) fn unset_piece(&mut self, coord: Coord, piece: Piece) {
         self.pieces[piece.to_index() as usize].unset(coord);
         self.all_pieces.unset(coord);
     }
@@ -471,18 +916,104 @@ impl OneSideBoard {
     pub fn has_piece(&self, coord: Coord) -> bool {
         self.all_pieces.get(coord)
     }
+
+    pub fn color(&self) -> bool {
+        self.color
+    }
+
+    /// Squares holding this side's pieces of kind `piece`.
+    pub fn pieces(&self, piece: PieceEnum) -> BitBoard {
+        self.pieces[Piece::new(piece, self.color).to_index() as usize]
+    }
+
+    /// Squares holding any of this side's pieces.
+    pub fn occupied(&self) -> BitBoard {
+        self.all_pieces
+    }
 }
 
 #[cfg(test)]
 // Test evaluation
-
 mod tests {
     use super::*;
-    use crate::r#move::Move;
 
     #[test]
     fn test_score() {
         let board = Board::new_game();
-        assert_eq!(board.evaluate(), 0);
+        assert_eq!(board.evaluate_white(), 0);
+    }
+
+    #[test]
+    fn test_sides() {
+        let board = Board::new_game();
+
+        assert!(board.side(true).color());
+        assert_eq!(board.side(true).pieces(PieceEnum::Pawn).get_board(), 0xff00);
+        assert_eq!(board.side(false).occupied().get_board(), 0xffff << 48);
+    }
+
+    #[test]
+    fn test_render_labels() {
+        let board = Board::new_game();
+
+        let lines: Vec<String> = board.render(false).lines().map(str::to_string).collect();
+        assert!(lines[0].starts_with("8 "));
+        assert_eq!(lines[8], "  a b c d e f g h");
+
+        let lines: Vec<String> = board.render(true).lines().map(str::to_string).collect();
+        assert!(lines[0].starts_with("1 "));
+        assert_eq!(lines[8], "  h g f e d c b a");
+    }
+
+    #[test]
+    fn test_check_evasions() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/4r3/4K3 w").unwrap();
+
+        assert!(board.is_check(true));
+        assert!(!board.is_checkmate(true));
+
+        let mut moves: Vec<String> = board.get_all_moves().iter().map(|m| m.to_str()).collect();
+        moves.sort();
+        assert_eq!(moves, vec!["e1d1", "e1e2", "e1f1"]);
+    }
+
+    #[test]
+    fn test_see() {
+        let board = Board::from_fen("4k3/8/8/3p4/4P3/8/8/4K3 w").unwrap();
+        assert_eq!(board.see(Move::from_str("e4d5")), 100);
+
+        let board = Board::from_fen("4k3/2p5/3p4/8/8/8/3R4/4K3 w").unwrap();
+        assert_eq!(board.see(Move::from_str("d2d6")), -400);
+        assert!(!board.see_ge(Move::from_str("d2d6"), 0));
+
+        // The rook on d1 only joins in once the one on d2 has captured
+        let board = Board::from_fen("3rk3/8/3p4/8/8/8/3R4/3RK3 w").unwrap();
+        assert_eq!(board.see(Move::from_str("d2d6")), 100);
+    }
+
+    #[test]
+    fn test_quiet_moves_are_not_captures() {
+        let board = Board::new_game();
+        let moves = board.get_all_moves();
+
+        assert_eq!(moves.len(), 20);
+        assert!(moves.iter().all(|m| !m.capture));
+    }
+
+    #[test]
+    fn test_hash_is_incremental() {
+        let mut board = Board::new_game();
+        let start = board.hash();
+
+        let m = Move::from_str("g1f3");
+        board.make_move(m);
+        assert_eq!(
+            board.hash(),
+            Board::from_fen(&board.to_fen()).unwrap().hash()
+        );
+        assert_ne!(board.hash(), start);
+
+        board.undo_move(m);
+        assert_eq!(board.hash(), start);
     }
 }
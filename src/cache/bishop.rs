@@ -1,5 +1,4 @@
 use crate::bitboard::{BitBoard, EMPTY};
-use lazy_static::lazy_static;
 
 pub fn get_bishop_moves(bishop_bitboard: BitBoard, blockers: BitBoard) -> BitBoard {
     let mut moves = EMPTY;
@@ -1,4 +1,7 @@
 use crate::bitboard::BitBoard;
+use crate::cache::bishop::get_bishop_moves;
+use crate::cache::knight::KNIGHT_MOVES;
+use crate::cache::rook::get_rook_moves;
 use crate::coord::Coord;
 use crate::r#move::Move;
 
@@ -19,3 +22,16 @@ pub fn bitboard_to_moves(from: Coord, bitboard: BitBoard, is_capture: bool) -> V
 
     moves
 }
+
+/// Squares attacked by the knight, bishop, rook or queen standing on `coord`.
+pub fn piece_attacks(index: u8, coord: Coord, occupied: BitBoard) -> BitBoard {
+    let square = BitBoard::from_coord(coord);
+
+    match index {
+        1 => KNIGHT_MOVES[coord.to_index() as usize],
+        2 => get_bishop_moves(square, occupied),
+        3 => get_rook_moves(square, occupied),
+        4 => get_bishop_moves(square, occupied) | get_rook_moves(square, occupied),
+        _ => BitBoard::new(),
+    }
+}
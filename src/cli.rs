@@ -0,0 +1,373 @@
+//! Command-line interface. Each subcommand maps to one of the engine's
+//! modes; without arguments the engine reads its mode from stdin, which is
+//! what chess GUIs expect.
+
+use chess_engine::{divide, Board, ConsoleOptions, Engine, SearchLimits, DEFAULT_BENCH_DEPTH};
+use std::time::{Duration, Instant};
+
+pub const USAGE: &str = "\
+Usage: chess-engine [command] [options]
+
+Commands:
+  uci                                   Run the UCI protocol on stdin
+  play                                  Let the engine play itself
+  console [--color C] [--depth N] [--movetime MS] [--fen FEN]
+                                        Play against the engine
+  bench [depth]                         Search the bench positions
+  perft <depth> [--fen FEN]             Count leaf nodes of the move tree
+  analyze --fen FEN [--depth N] [--movetime MS] [--multipv N]
+                                        Search a position
+  eval [--fen FEN]                      Print the evaluation breakdown
+  tune <positions> [output] [passes]    Tune the evaluation parameters
+  datagen <output> [games] [depth] [nodes]
+                                        Generate self-play training data
+  epd <suite> [depth] [movetime]        Run an EPD test suite
+  help                                  Print this message
+
+Without a command, the first line of stdin selects the mode.";
+
+const DEFAULT_ANALYZE_DEPTH: u32 = 6;
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// No arguments: read the mode from stdin.
+    Stdin,
+    Help,
+    Uci,
+    Play,
+    Console {
+        options: ConsoleOptions,
+        fen: Option<String>,
+    },
+    Bench {
+        depth: u32,
+    },
+    Perft {
+        depth: u32,
+        fen: Option<String>,
+    },
+    Analyze {
+        fen: String,
+        depth: Option<u32>,
+        movetime: Option<Duration>,
+        multipv: usize,
+    },
+    Eval {
+        fen: Option<String>,
+    },
+    /// Commands taking the same arguments as their stdin form.
+    Tune(String),
+    Datagen(String),
+    Epd(String),
+}
+
+/// Arguments after the command name: positional values and `--flag value`
+/// options. A FEN may be passed as one quoted argument or as several.
+struct Args {
+    positional: Vec<String>,
+    options: Vec<(String, String)>,
+}
+
+impl Args {
+    fn parse(args: &[String], flags: &[&str]) -> Result<Args, String> {
+        let mut positional = Vec::new();
+        let mut options: Vec<(String, String)> = Vec::new();
+        let mut args = args.iter().peekable();
+
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(flag) if flags.contains(&flag) => {
+                    let mut value = Vec::new();
+                    while let Some(next) = args.next_if(|next| !next.starts_with("--")) {
+                        value.push(next.as_str());
+                        // Only FENs span several arguments
+                        if flag != "fen" {
+                            break;
+                        }
+                    }
+
+                    if value.is_empty() {
+                        return Err(format!("missing value for --{}", flag));
+                    }
+
+                    options.push((flag.to_string(), value.join(" ")));
+                }
+                Some(_) => return Err(format!("unknown option {}", arg)),
+                None => positional.push(arg.clone()),
+            }
+        }
+
+        Ok(Args {
+            positional,
+            options,
+        })
+    }
+
+    fn option(&self, flag: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(name, _)| name == flag)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn number<T: std::str::FromStr>(value: &str, what: &str) -> Result<T, String> {
+        value
+            .parse()
+            .map_err(|_| format!("invalid {}: {}", what, value))
+    }
+
+    fn fen(&self) -> Result<Option<String>, String> {
+        match self.option("fen") {
+            Some(fen) => match Board::from_fen(fen) {
+                Some(_) => Ok(Some(fen.to_string())),
+                None => Err(format!("invalid FEN: {}", fen)),
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+/// Parses the arguments following the program name.
+pub fn parse_args(args: &[String]) -> Result<Command, String> {
+    let (command, rest) = match args.split_first() {
+        Some((command, rest)) => (command.as_str(), rest),
+        None => return Ok(Command::Stdin),
+    };
+
+    let flags: &[&str] = match command {
+        "perft" | "eval" => &["fen"],
+        "analyze" => &["fen", "depth", "movetime", "multipv"],
+        "console" => &["fen", "color", "depth", "movetime"],
+        _ => &[],
+    };
+
+    let stdin_form = || format!("{} {}", command, rest.join(" "));
+
+    match command {
+        "help" | "--help" | "-h" => Ok(Command::Help),
+        "uci" => Ok(Command::Uci),
+        "play" => Ok(Command::Play),
+        "tune" => Ok(Command::Tune(stdin_form())),
+        "datagen" => Ok(Command::Datagen(stdin_form())),
+        "epd" => Ok(Command::Epd(stdin_form())),
+        "bench" | "perft" | "analyze" | "eval" | "console" => {
+            let args = Args::parse(rest, flags)?;
+
+            match command {
+                "bench" => Ok(Command::Bench {
+                    depth: match args.positional.first() {
+                        Some(depth) => Args::number(depth, "depth")?,
+                        None => DEFAULT_BENCH_DEPTH,
+                    },
+                }),
+                "perft" => Ok(Command::Perft {
+                    depth: match Args::number(
+                        args.positional.first().ok_or("missing perft depth")?,
+                        "depth",
+                    )? {
+                        0 => return Err("perft depth must be at least 1".to_string()),
+                        depth => depth,
+                    },
+                    fen: args.fen()?,
+                }),
+                "analyze" => Ok(Command::Analyze {
+                    fen: args.fen()?.ok_or("analyze needs --fen")?,
+                    depth: args
+                        .option("depth")
+                        .map(|d| Args::number(d, "depth"))
+                        .transpose()?,
+                    movetime: args
+                        .option("movetime")
+                        .map(|t| Args::number(t, "movetime").map(Duration::from_millis))
+                        .transpose()?,
+                    multipv: match args.option("multipv") {
+                        Some(multipv) => Args::number(multipv, "multipv")?,
+                        None => 1,
+                    },
+                }),
+                "console" => {
+                    let mut options = ConsoleOptions::default();
+                    if let Some(color) = args.option("color") {
+                        options.human = parse_color(color)?;
+                    }
+                    if let Some(depth) = args.option("depth") {
+                        options.depth = Args::number(depth, "depth")?;
+                    }
+                    if let Some(movetime) = args.option("movetime") {
+                        options.movetime =
+                            Some(Duration::from_millis(Args::number(movetime, "movetime")?));
+                    }
+
+                    Ok(Command::Console {
+                        options,
+                        fen: args.fen()?,
+                    })
+                }
+                _ => Ok(Command::Eval { fen: args.fen()? }),
+            }
+        }
+        _ => Err(format!("unknown command: {}", command)),
+    }
+}
+
+/// Parses "white" or "black", or their first letter.
+fn parse_color(text: &str) -> Result<bool, String> {
+    match text.to_lowercase().as_str() {
+        "white" | "w" => Ok(true),
+        "black" | "b" => Ok(false),
+        _ => Err(format!("invalid color: {}", text)),
+    }
+}
+
+fn board_from(fen: Option<&str>) -> Board {
+    fen.and_then(Board::from_fen)
+        .unwrap_or_else(Board::new_game)
+}
+
+/// Runs a parsed command. Errors are for the caller to report.
+pub fn run(engine: &mut Engine, command: Command) -> Result<(), String> {
+    match command {
+        Command::Stdin => engine.run(),
+        Command::Help => println!("{}", USAGE),
+        Command::Uci => engine.run_uci(),
+        Command::Play => engine.play(),
+        Command::Console { options, fen } => {
+            engine.console(board_from(fen.as_deref()), options);
+        }
+        Command::Bench { depth } => engine.bench(depth),
+        Command::Perft { depth, fen } => {
+            let mut board = board_from(fen.as_deref());
+            let start = Instant::now();
+            let mut total = 0;
+
+            for (m, nodes) in divide(&mut board, depth) {
+                println!("{}: {}", m.to_str(), nodes);
+                total += nodes;
+            }
+
+            println!();
+            println!("Nodes searched: {}", total);
+            println!("Time: {} ms", start.elapsed().as_millis());
+        }
+        Command::Analyze {
+            fen,
+            depth,
+            movetime,
+            multipv,
+        } => {
+            let limits = SearchLimits {
+                depth: depth.or(movetime.is_none().then_some(DEFAULT_ANALYZE_DEPTH)),
+                movetime,
+                ..SearchLimits::default()
+            };
+
+            engine.set_position(board_from(Some(&fen)));
+            engine.set_uci(true);
+            engine.set_multipv(multipv);
+
+            if engine.get_all_moves().is_empty() {
+                return Err("no legal moves in this position".to_string());
+            }
+
+            let result = engine.go(&limits);
+
+            match result.ponder_move {
+                Some(reply) => println!(
+                    "bestmove {} ponder {}",
+                    result.best_move.to_str(),
+                    reply.to_str()
+                ),
+                None => println!("bestmove {}", result.best_move.to_str()),
+            }
+        }
+        Command::Eval { fen } => println!("{}", board_from(fen.as_deref()).trace()),
+        Command::Tune(input) => engine.tune(&input)?,
+        Command::Datagen(input) => engine.datagen(&input)?,
+        Command::Epd(input) => engine.epd(&input)?,
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<Command, String> {
+        let args: Vec<String> = line.split_whitespace().map(str::to_string).collect();
+        parse_args(&args)
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(parse(""), Ok(Command::Stdin));
+        assert_eq!(parse("--help"), Ok(Command::Help));
+        assert_eq!(parse("uci"), Ok(Command::Uci));
+        assert_eq!(
+            parse("bench"),
+            Ok(Command::Bench {
+                depth: DEFAULT_BENCH_DEPTH
+            })
+        );
+        assert_eq!(
+            parse("tune data.txt out.txt"),
+            Ok(Command::Tune("tune data.txt out.txt".to_string()))
+        );
+        assert_eq!(
+            parse("console --color black --depth 2"),
+            Ok(Command::Console {
+                options: ConsoleOptions {
+                    human: false,
+                    depth: 2,
+                    movetime: None,
+                },
+                fen: None,
+            })
+        );
+        assert!(parse("console --color red").is_err());
+        assert!(parse("fly").is_err());
+        assert!(parse("bench deep").is_err());
+    }
+
+    #[test]
+    fn test_parse_fen_options() {
+        assert_eq!(
+            parse("perft 3 --fen 4k3/8/8/8/8/8/8/4K2R w"),
+            Ok(Command::Perft {
+                depth: 3,
+                fen: Some("4k3/8/8/8/8/8/8/4K2R w".to_string())
+            })
+        );
+        assert_eq!(
+            parse("analyze --fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1 --movetime 100"),
+            Ok(Command::Analyze {
+                fen: "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1".to_string(),
+                depth: None,
+                movetime: Some(Duration::from_millis(100)),
+                multipv: 1,
+            })
+        );
+        assert!(parse("perft").is_err());
+        assert!(parse("perft 0").is_err());
+        assert!(parse("analyze --depth 3").is_err());
+        assert!(parse("analyze --fen --depth 3").is_err());
+        assert!(parse("eval --fen nonsense w").is_err());
+        assert!(parse("eval --color white").is_err());
+    }
+
+    #[test]
+    fn test_analyze_movetime() {
+        let mut engine = Engine::new();
+        let command = Command::Analyze {
+            fen: "6k1/5ppp/8/8/8/8/8/R5K1 w".to_string(),
+            depth: None,
+            movetime: Some(Duration::from_millis(50)),
+            multipv: 2,
+        };
+
+        let start = Instant::now();
+        assert_eq!(run(&mut engine, command), Ok(()));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,269 @@
+//! Human against engine games in the terminal. The human enters moves in
+//! SAN or in UCI coordinate notation, along with a few commands.
+
+use crate::board::Board;
+use crate::engine::Engine;
+use crate::limits::SearchLimits;
+use crate::pgn::result_of;
+use crate::r#move::Move;
+use std::io::Write;
+use std::time::Duration;
+
+pub const CONSOLE_HELP: &str = "\
+Enter moves in SAN (Nf3, exd5, O-O) or UCI notation (g1f3). Commands:
+  undo          Take back your last move
+  hint          Suggest a move
+  flip          Turn the board around
+  fen           Print the position as FEN
+  depth <n>     Set the engine search depth
+  movetime <ms> Limit the engine thinking time, 0 for no limit
+  resign        Give up the game
+  quit          Leave without a result
+  help          Print this message";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConsoleOptions {
+    /// Color of the human, true for white.
+    pub human: bool,
+    /// Engine strength: search depth, and optionally a time limit per move.
+    pub depth: u32,
+    pub movetime: Option<Duration>,
+}
+
+impl Default for ConsoleOptions {
+    fn default() -> Self {
+        ConsoleOptions {
+            human: true,
+            depth: 4,
+            movetime: None,
+        }
+    }
+}
+
+/// Resolves `text` against the legal moves, in UCI notation such as `e2e4`
+/// or `e7e8q`, or else in SAN.
+pub fn parse_move(board: &Board, text: &str) -> Result<Move, String> {
+    let text = text.trim();
+
+    match board.parse_uci(text) {
+        Err(e) if e.starts_with("invalid") => board.parse_san(text),
+        result => result,
+    }
+}
+
+/// What the game loop should do after a command.
+#[derive(Debug, PartialEq)]
+pub enum Status {
+    Continue,
+    /// The game is over, with a PGN result such as `1-0`.
+    Stop(&'static str),
+}
+
+pub struct Console<'a> {
+    engine: &'a mut Engine,
+    options: ConsoleOptions,
+    flipped: bool,
+}
+
+impl<'a> Console<'a> {
+    /// A new game from `board`, the human seeing the board from their side.
+    pub fn new(engine: &'a mut Engine, board: Board, options: ConsoleOptions) -> Console<'a> {
+        engine.set_position(board);
+        engine.clear_hash();
+
+        Console {
+            engine,
+            options,
+            flipped: !options.human,
+        }
+    }
+
+    fn is_human_turn(&self) -> bool {
+        self.engine.board.turn == self.options.human
+    }
+
+    fn print_board(&self) {
+        println!("{}", self.engine.board.render(self.flipped));
+    }
+
+    fn engine_move(&mut self) -> Move {
+        let limits = SearchLimits {
+            depth: Some(self.options.depth),
+            movetime: self.options.movetime,
+            ..SearchLimits::default()
+        };
+
+        self.engine.go(&limits).best_move
+    }
+
+    /// Plays until the game ends or the human leaves, reading from stdin.
+    /// Returns the PGN result.
+    pub fn run(&mut self) -> &'static str {
+        println!("Type 'help' for the list of commands.");
+        self.print_board();
+
+        let result = loop {
+            if self.engine.board.is_game_over() {
+                break result_of(&self.engine.board);
+            }
+
+            if !self.is_human_turn() {
+                let m = self.engine_move();
+                println!("Engine plays {}", self.engine.board.move_to_san(m));
+
+                self.engine.board.make_move(m);
+                self.print_board();
+                continue;
+            }
+
+            print!("> ");
+            std::io::stdout().flush().ok();
+
+            let mut input = String::new();
+            match std::io::stdin().read_line(&mut input) {
+                Ok(0) | Err(_) => break "*",
+                Ok(_) => {}
+            }
+
+            match self.command(&input) {
+                Ok(Status::Continue) => {}
+                Ok(Status::Stop(result)) => break result,
+                Err(e) => println!("{}", e),
+            }
+        };
+
+        let mut game = self.engine.to_pgn();
+        let (white, black) = if self.options.human {
+            ("Human", "ChessEngine")
+        } else {
+            ("ChessEngine", "Human")
+        };
+        game.set_tag("White", white);
+        game.set_tag("Black", black);
+        game.set_result(result);
+
+        println!("{}", game);
+
+        result
+    }
+
+    /// Handles one line of input on the human's turn.
+    pub fn command(&mut self, input: &str) -> Result<Status, String> {
+        let mut args = input.split_whitespace();
+        let command = match args.next() {
+            Some(command) => command,
+            None => return Ok(Status::Continue),
+        };
+
+        match command {
+            "help" => println!("{}", CONSOLE_HELP),
+            "quit" => return Ok(Status::Stop("*")),
+            "resign" => {
+                let result = if self.options.human { "0-1" } else { "1-0" };
+                println!("You resign, {}", result);
+                return Ok(Status::Stop(result));
+            }
+            "fen" => println!("{}", self.engine.board.to_fen()),
+            "flip" => {
+                self.flipped = !self.flipped;
+                self.print_board();
+            }
+            "hint" => {
+                let m = self.engine_move();
+                println!("Hint: {}", self.engine.board.move_to_san(m));
+            }
+            "undo" => {
+                self.undo()?;
+                self.print_board();
+            }
+            "depth" => {
+                let depth = args.next().and_then(|d| d.parse().ok());
+                match depth {
+                    Some(depth) if depth > 0 => self.options.depth = depth,
+                    _ => return Err("Usage: depth <n>, n at least 1".to_string()),
+                }
+            }
+            "movetime" => match args.next().and_then(|t| t.parse().ok()) {
+                Some(0) => self.options.movetime = None,
+                Some(ms) => self.options.movetime = Some(Duration::from_millis(ms)),
+                None => return Err("Usage: movetime <ms>".to_string()),
+            },
+            _ => {
+                let m = parse_move(&self.engine.board, input)?;
+                self.engine.board.make_move(m);
+                self.print_board();
+            }
+        }
+
+        Ok(Status::Continue)
+    }
+
+    /// Takes back moves until it is the human's turn again, at least one
+    /// of them being the human's.
+    fn undo(&mut self) -> Result<(), String> {
+        let mut board = self.engine.board.clone();
+
+        loop {
+            let m = match board.last_move() {
+                Some(last) => last.m,
+                None => return Err("nothing to undo".to_string()),
+            };
+
+            board.undo_move(m);
+
+            if board.turn == self.options.human {
+                break;
+            }
+        }
+
+        self.engine.board = board;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_move() {
+        let board = Board::new_game();
+
+        assert_eq!(parse_move(&board, "e2e4"), Ok(Move::from_str("e2e4")));
+        assert_eq!(parse_move(&board, "Nf3"), Ok(Move::from_str("g1f3")));
+        assert!(parse_move(&board, "e2e5").is_err());
+        assert!(parse_move(&board, "e9e4").is_err());
+        assert!(parse_move(&board, "zz").is_err());
+
+        // Only moves of the legal move list, so no promotions
+        let board = Board::from_fen("7k/P7/8/8/8/8/8/K7 w").unwrap();
+        assert!(parse_move(&board, "a8=Q").is_err());
+        assert!(board
+            .get_all_moves()
+            .contains(&parse_move(&board, "a7a8q").unwrap()));
+    }
+
+    #[test]
+    fn test_commands_and_undo() {
+        let mut engine = Engine::new();
+        let options = ConsoleOptions {
+            depth: 1,
+            ..ConsoleOptions::default()
+        };
+        let mut console = Console::new(&mut engine, Board::new_game(), options);
+
+        assert!(console.command("undo").is_err());
+        assert!(console.command("e5").is_err());
+        assert!(console.command("depth 0").is_err());
+        assert_eq!(console.command("e4"), Ok(Status::Continue));
+
+        // The engine replies, then both moves are taken back
+        let m = console.engine_move();
+        console.engine.board.make_move(m);
+        assert_eq!(console.command("undo"), Ok(Status::Continue));
+        assert_eq!(console.engine.board.to_fen(), Board::new_game().to_fen());
+
+        assert_eq!(console.command("resign"), Ok(Status::Stop("0-1")));
+    }
+}
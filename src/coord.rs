@@ -1,4 +1,4 @@
-#[derive(PartialEq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
 pub struct Coord {
     pub x: u8,
     pub y: u8,
@@ -16,18 +16,16 @@ impl Coord {
         }
     }
 
+    /// Parses a square such as `e4`, returning `None` for anything else.
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Coord> {
-        if s.len() != 2 {
-            return None;
+        match s.as_bytes() {
+            [file @ b'a'..=b'h', rank @ b'1'..=b'8'] => Some(Coord::new(file - b'a', rank - b'1')),
+            _ => None,
         }
-
-        let x = s.chars().nth(0).unwrap() as u8 - 97;
-        let y = s.chars().nth(1).unwrap() as u8 - 49;
-
-        Some(Coord::new(x, y))
     }
 
-    pub fn to_str(&self) -> String {
+    pub fn to_str(self) -> String {
         // Should be wither letter and number
         // We can use the ASCII table to convert
 
@@ -40,7 +38,7 @@ impl Coord {
     }
 
     #[inline(always)]
-    pub fn to_index(&self) -> u8 {
+    pub fn to_index(self) -> u8 {
         self.x + self.y * 8
     }
 
@@ -0,0 +1,238 @@
+//! Training data from self-play.
+//!
+//! Games start with a few random moves and are then played by the engine
+//! at a fixed depth, each search also stopping at a fixed number of nodes
+//! if one is set. Every quiet position of a game is written on its own
+//! line, once the game is over, as
+//!
+//! ```text
+//! <fen> | <score> | <result>
+//! ```
+//!
+//! where `<fen>` is the piece placement and side to move, `<score>` the
+//! search score in centipawns from white's side, and `<result>` the game
+//! result from white's side: `1.0`, `0.5` or `0.0`. Positions in check,
+//! where the best move is a capture, or with a mate score are left out.
+
+use crate::board::Board;
+use crate::engine::Engine;
+use crate::limits::SearchLimits;
+use crate::search::{MATE_SCORE, MAX_PLY};
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Settings of `generate`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DatagenOptions {
+    pub games: u32,
+    pub depth: u32,
+    /// Nodes after which each search stops, before reaching `depth`.
+    pub nodes: Option<u64>,
+    /// Random moves played before the engine takes over.
+    pub random_plies: u32,
+    /// Games still going after this many plies are scored as draws.
+    pub max_plies: u32,
+    pub seed: u64,
+}
+
+impl Default for DatagenOptions {
+    fn default() -> Self {
+        Self {
+            games: 100,
+            depth: 4,
+            nodes: None,
+            random_plies: 8,
+            max_plies: 300,
+            seed: 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+}
+
+/// Progress after each game, passed to the caller of `generate`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GameReport {
+    /// Number of the game, from 1 to `DatagenOptions::games`.
+    pub game: u32,
+    /// Result from white's side.
+    pub result: f64,
+    /// Positions the game contributed.
+    pub positions: usize,
+}
+
+/// Xorshift generator, seeded so that runs can be reproduced.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+/// Only kings left, neither side can ever mate.
+fn bare_kings(board: &Board) -> bool {
+    (0..5u8).all(|index| {
+        board.get_piece_index_bitboard(index, true).get_board() == 0
+            && board.get_piece_index_bitboard(index, false).get_board() == 0
+    })
+}
+
+/// A random opening, or `None` if it happened to end the game.
+fn random_opening(rng: &mut Rng, plies: u32) -> Option<Board> {
+    let mut board = Board::new_game();
+
+    for _ in 0..plies {
+        let moves = board.get_all_moves();
+        if moves.is_empty() {
+            return None;
+        }
+
+        board.make_move(moves[(rng.next() % moves.len() as u64) as usize]);
+    }
+
+    (!board.is_game_over()).then_some(board)
+}
+
+/// Plays one game from `board`, returning the quiet positions with their
+/// white-relative scores and the result from white's side.
+fn play_game(
+    engine: &mut Engine,
+    board: Board,
+    options: &DatagenOptions,
+) -> (Vec<(String, i16)>, f64) {
+    let mut positions = Vec::new();
+    let mut seen: HashMap<u64, u32> = HashMap::new();
+
+    let limits = SearchLimits {
+        depth: Some(options.depth),
+        nodes: options.nodes,
+        ..SearchLimits::default()
+    };
+
+    engine.set_position(board);
+    engine.clear_hash();
+
+    for _ in 0..options.max_plies {
+        let board = &engine.board;
+
+        if board.is_checkmate(board.turn) {
+            return (positions, if board.turn { 0.0 } else { 1.0 });
+        }
+        if board.is_game_over() || bare_kings(board) {
+            return (positions, 0.5);
+        }
+
+        let repetitions = seen.entry(board.hash()).or_default();
+        *repetitions += 1;
+        if *repetitions >= 3 {
+            return (positions, 0.5);
+        }
+
+        let result = engine.go(&limits);
+        let (m, score) = (result.best_move, result.score);
+        let board = &engine.board;
+        let quiet = !board.is_check(board.turn) && !m.capture;
+
+        if quiet && (score.unsigned_abs() as u32) < MATE_SCORE as u32 - MAX_PLY {
+            let white_score = if board.turn { score } else { -score };
+            positions.push((board.to_fen(), white_score));
+        }
+
+        engine.make_move(m);
+    }
+
+    (positions, 0.5)
+}
+
+/// Plays `options.games` games and writes their positions to `output` in
+/// the format described above, calling `progress` after each game.
+/// Returns the number of positions written.
+pub fn generate(
+    options: &DatagenOptions,
+    output: &mut impl Write,
+    mut progress: impl FnMut(&GameReport),
+) -> std::io::Result<usize> {
+    let mut rng = Rng(options.seed.max(1));
+    let mut engine = Engine::new();
+    let mut written = 0;
+
+    for game in 1..=options.games {
+        let board = loop {
+            if let Some(board) = random_opening(&mut rng, options.random_plies) {
+                break board;
+            }
+        };
+
+        let (positions, result) = play_game(&mut engine, board, options);
+
+        for (fen, score) in &positions {
+            writeln!(output, "{} | {} | {:.1}", fen, score, result)?;
+        }
+        written += positions.len();
+
+        progress(&GameReport {
+            game,
+            result,
+            positions: positions.len(),
+        });
+    }
+
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tuner::parse_result;
+
+    #[test]
+    fn test_generated_lines_parse() {
+        let options = DatagenOptions {
+            games: 1,
+            depth: 1,
+            max_plies: 12,
+            ..DatagenOptions::default()
+        };
+        let mut output = Vec::new();
+        let mut reports = Vec::new();
+
+        let written = generate(&options, &mut output, |report| reports.push(*report)).unwrap();
+        let text = String::from_utf8(output).unwrap();
+
+        assert_eq!(text.lines().count(), written);
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].positions, written);
+        for line in text.lines() {
+            let board = Board::from_fen(line).unwrap();
+
+            assert!(!board.is_check(board.turn));
+            assert!(parse_result(line).is_some());
+        }
+    }
+
+    #[test]
+    fn test_node_limit() {
+        let options = DatagenOptions {
+            games: 1,
+            depth: 64,
+            nodes: Some(500),
+            max_plies: 6,
+            ..DatagenOptions::default()
+        };
+        let mut output = Vec::new();
+
+        let written = generate(&options, &mut output, |_| {}).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap().lines().count(), written);
+    }
+
+    #[test]
+    fn test_checkmate_result() {
+        let mut engine = Engine::new();
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w").unwrap();
+
+        let (_, result) = play_game(&mut engine, board, &DatagenOptions::default());
+        assert_eq!(result, 1.0);
+    }
+}
@@ -1,45 +1,79 @@
+use crate::bench::{print_bench, run_bench};
 use crate::board::Board;
+use crate::console::{Console, ConsoleOptions};
+use crate::datagen::{generate, DatagenOptions};
+use crate::epd::{load_epd, run_suite};
+use crate::evaluator::{
+    evaluator_from_name, ClassicEvaluator, Evaluator, NnueEvaluator, TunedEvaluator,
+};
+use crate::limits::SearchLimits;
+use crate::nnue::Network;
+use crate::params::EvalParams;
+use crate::pgn::{result_of, PgnGame};
 use crate::piece::PieceEnum;
 use crate::r#move::Move;
-use std::collections::HashMap;
+use crate::search::{Extensions, InfoCallback, SearchResult, SearchThread, SharedSearch, UciInfo};
+use crate::tt::{TranspositionTable, DEFAULT_TT_SIZE_MB};
+use crate::tuner::{load_positions, Tuner};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-pub struct TranspositionTable {
-    entries: HashMap<(String, u32), i16>,
-}
-
-impl TranspositionTable {
-    pub fn new() -> Self {
-        Self {
-            entries: HashMap::new(),
-        }
-    }
-
-    pub fn get(&self, key: &(String, u32)) -> Option<i16> {
-        self.entries.get(key).copied()
-    }
-
-    pub fn set(&mut self, key: (String, u32), value: i16) {
-        self.entries.insert(key, value);
-    }
-}
+pub const MAX_THREADS: usize = 64;
+pub const MAX_MULTIPV: usize = 256;
 
 pub struct Engine {
-    pub board: Board,
+    /// The game being played, only replaced through `set_position` so that
+    /// `start_fen` stays in sync.
+    pub(crate) board: Board,
+    /// Position the current game started from, for game records.
+    start_fen: String,
+    pub extensions: Extensions,
+    evaluator: Box<dyn Evaluator>,
     tt: TranspositionTable,
+    stop: Arc<AtomicBool>,
+    pondering: Arc<AtomicBool>,
+    threads: usize,
+    pool: Option<ThreadPool>,
+    nodes: u64,
+    seldepth: u32,
+    multipv: usize,
+    uci: bool,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Engine::new()
+    }
 }
 
 impl Engine {
     pub fn new() -> Engine {
-        Engine {
-            board: Board::new_game(),
-            tt: TranspositionTable::new(),
-        }
+        Engine::with_board(Board::new_game())
     }
 
     pub fn from_fen(fen: &str) -> Engine {
+        Engine::with_board(Board::from_fen(fen).unwrap())
+    }
+
+    fn with_board(board: Board) -> Engine {
         Engine {
-            board: Board::from_fen(fen).unwrap(),
-            tt: TranspositionTable::new(),
+            start_fen: board.to_fen(),
+            board,
+            extensions: Extensions::default(),
+            evaluator: Box::new(ClassicEvaluator::new()),
+            tt: TranspositionTable::new(DEFAULT_TT_SIZE_MB),
+            stop: Arc::new(AtomicBool::new(false)),
+            pondering: Arc::new(AtomicBool::new(false)),
+            threads: 1,
+            pool: None,
+            nodes: 0,
+            seldepth: 0,
+            multipv: 1,
+            uci: false,
         }
     }
 
@@ -47,59 +81,320 @@ impl Engine {
         self.board.get_all_moves()
     }
 
-    fn negamax(&mut self, depth: u32, alpha: i16, beta: i16) -> i16 {
-        if depth == 0 {
-            return self.board.evaluate();
-        }
+    /// Number of nodes visited by the last search, summed over all threads.
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    /// Deepest ply reached by the main thread of the last search, extensions
+    /// included.
+    pub fn seldepth(&self) -> u32 {
+        self.seldepth
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
 
-        let mut alpha = alpha;
-        let mut max = -9999;
+    /// Sets the number of search threads. With a single thread the search
+    /// runs on the calling thread and is fully deterministic.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.clamp(1, MAX_THREADS);
+        self.pool = if self.threads > 1 {
+            ThreadPoolBuilder::new()
+                .num_threads(self.threads)
+                .build()
+                .ok()
+        } else {
+            None
+        };
+    }
+
+    pub fn multipv(&self) -> usize {
+        self.multipv
+    }
+
+    /// Sets how many best moves searches report, each with its score and
+    /// principal variation, in `SearchResult::lines`. More lines make each
+    /// iteration slower.
+    pub fn set_multipv(&mut self, multipv: usize) {
+        self.multipv = multipv.clamp(1, MAX_MULTIPV);
+    }
+
+    /// Whether searches print UCI `info` lines.
+    pub fn set_uci(&mut self, uci: bool) {
+        self.uci = uci;
+    }
 
-        let key = (self.board.to_fen(), depth);
-        if let Some(value) = self.tt.get(&key) {
-            return value;
+    pub fn evaluator(&self) -> &dyn Evaluator {
+        self.evaluator.as_ref()
+    }
+
+    /// Replaces the evaluation used by the search. Stored scores came from
+    /// the old evaluation, so the transposition table is cleared.
+    pub fn set_evaluator(&mut self, evaluator: Box<dyn Evaluator>) {
+        self.evaluator = evaluator;
+        self.clear_hash();
+    }
+
+    /// Clears the transposition table, forgetting everything learned from
+    /// previous searches.
+    pub fn clear_hash(&mut self) {
+        self.tt.clear();
+    }
+
+    /// A flag that stops the running search when set, for example from
+    /// another thread during an infinite search.
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    /// A flag set while a `ponder` search runs. Clearing it when the
+    /// opponent plays the expected move (ponderhit) turns the search into a
+    /// normal one, its time limits counting from then.
+    pub fn pondering_flag(&self) -> Arc<AtomicBool> {
+        self.pondering.clone()
+    }
+
+    /// Resets the flags controlling a search before it starts, and before
+    /// anyone may set them while it runs.
+    fn start_search(&self, limits: &SearchLimits) {
+        self.stop.store(false, Ordering::Relaxed);
+        self.pondering.store(limits.ponder, Ordering::Relaxed);
+    }
+
+    /// Searches to `depth`. Returns the best move with its score for the
+    /// side to move.
+    pub fn search(&mut self, depth: u32) -> (Move, i16) {
+        let result = self.go(&SearchLimits::depth(depth));
+        (result.best_move, result.score)
+    }
+
+    /// Searches within `limits`, printing UCI `info` lines in UCI mode.
+    /// With `infinite` or `ponder`, blocks until another thread sets the
+    /// stop flag or, for `ponder`, clears the pondering flag.
+    pub fn go(&mut self, limits: &SearchLimits) -> SearchResult {
+        self.start_search(limits);
+
+        if self.uci {
+            self.run_search(limits, Some(&mut UciInfo))
+        } else {
+            self.run_search(limits, None)
         }
+    }
 
-        for m in self.get_all_moves() {
-            let fen = self.board.to_fen();
-            self.board.make_move(m);
+    /// Searches within `limits`, reporting progress to `callback`.
+    pub fn go_with(
+        &mut self,
+        limits: &SearchLimits,
+        callback: &mut dyn InfoCallback,
+    ) -> SearchResult {
+        self.start_search(limits);
+        self.run_search(limits, Some(callback))
+    }
 
-            let score = -self.negamax(depth - 1, -beta, -alpha);
+    /// Lazy SMP: every helper thread runs its own iterative deepening on a
+    /// copy of the board, sharing only the transposition table. Helpers are
+    /// stopped as soon as the main thread finishes, and only the main
+    /// thread's result is used.
+    ///
+    /// Time limits are enforced by a timer thread setting the stop flag.
+    /// Callers reset the flags with `start_search` first. Infinite and
+    /// pondering searches return only once stopped or, for pondering, once
+    /// the pondering flag is cleared.
+    fn run_search(
+        &mut self,
+        limits: &SearchLimits,
+        callback: Option<&mut dyn InfoCallback>,
+    ) -> SearchResult {
+        let start = Instant::now();
+        let nodes = AtomicU64::new(0);
+        let finished = AtomicBool::new(false);
+        let depth = limits.max_depth();
 
-            self.board.undo_move(m);
+        let shared = || SharedSearch {
+            tt: &self.tt,
+            stop: &self.stop,
+            nodes: &nodes,
+        };
 
-            max = max.max(score);
+        // Boards keep the accumulators of the network, if any, and the score
+        // with the evaluator's parameters up to date
+        let mut board = self.board.clone();
+        board.set_network(self.evaluator.network());
+        board.set_params(self.evaluator.params());
 
-            alpha = alpha.max(score);
-            if alpha >= beta {
-                break;
+        let mut main = SearchThread::new(board.clone(), self.extensions, shared())
+            .with_evaluator(self.evaluator.clone_box())
+            .with_limits(limits)
+            .with_multipv(self.multipv);
+        if let Some(callback) = callback {
+            main = main.with_callback(callback);
+        }
+
+        let (best_move, score) = std::thread::scope(|timer| {
+            if let Some(budget) = limits.time_budget(self.board.turn) {
+                let (stop, pondering, finished) = (&self.stop, &self.pondering, &finished);
+                let ponder = limits.ponder;
+
+                timer.spawn(move || {
+                    // While pondering, the clock has not started yet. It
+                    // starts once this thread sees the ponderhit, which may
+                    // have come before the thread even started.
+                    while pondering.load(Ordering::Relaxed) && !finished.load(Ordering::Relaxed) {
+                        std::thread::sleep(Duration::from_millis(1));
+                    }
+                    let start = if ponder { Instant::now() } else { start };
+
+                    while !finished.load(Ordering::Relaxed) && start.elapsed() < budget {
+                        std::thread::sleep(Duration::from_millis(1));
+                    }
+
+                    // A search over before its time, possibly while still
+                    // pondering, must keep waiting for ponderhit
+                    if !finished.load(Ordering::Relaxed) {
+                        stop.store(true, Ordering::Relaxed);
+                    }
+                });
             }
+
+            let result = match &self.pool {
+                Some(pool) => pool.scope(|scope| {
+                    for id in 1..self.threads {
+                        let mut helper =
+                            SearchThread::new(board.clone(), self.extensions, shared())
+                                .with_evaluator(self.evaluator.clone_box())
+                                .with_limits(limits)
+                                .helper();
+                        // Odd helpers look one ply deeper so threads desynchronize
+                        let helper_depth = depth + (id % 2) as u32;
+                        scope.spawn(move |_| {
+                            helper.iterate(helper_depth);
+                        });
+                    }
+
+                    let result = main.iterate(depth);
+                    self.wait_for_stop(limits);
+                    self.stop.store(true, Ordering::Relaxed);
+                    result
+                }),
+                None => {
+                    let result = main.iterate(depth);
+                    self.wait_for_stop(limits);
+                    result
+                }
+            };
+
+            finished.store(true, Ordering::Relaxed);
+            result
+        });
+
+        // The first iteration always completes, so there are lines unless
+        // there is no move to search
+        let lines = main.lines.clone();
+        let pv = lines.first().map_or_else(Vec::new, |line| line.pv.clone());
+        let ponder_move = match pv.as_slice() {
+            [_, reply, ..] => Some(*reply),
+            [m] => self.expected_reply(*m),
+            [] => None,
+        };
+
+        self.nodes = nodes.load(Ordering::Relaxed);
+        self.seldepth = main.seldepth;
+
+        SearchResult {
+            best_move: pv.first().copied().unwrap_or(best_move),
+            ponder_move,
+            score,
+            pv,
+            lines,
+            depth: main.completed_depth,
+            seldepth: main.seldepth,
+            nodes: self.nodes,
+            time: start.elapsed(),
         }
+    }
 
-        // Store the result in the transposition table
-        self.tt.set(key, max);
+    /// Infinite searches only end with `stop`, and pondering ones with
+    /// `stop` or `ponderhit`, even once the search itself is over. Called
+    /// before helper threads are stopped, since that sets the stop flag.
+    fn wait_for_stop(&self, limits: &SearchLimits) {
+        while (limits.infinite || self.pondering.load(Ordering::Relaxed))
+            && !self.stop.load(Ordering::Relaxed)
+        {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
 
-        max
+    /// The best reply to `m` stored in the transposition table, for when
+    /// the principal variation is cut short.
+    fn expected_reply(&self, m: Move) -> Option<Move> {
+        let mut board = self.board.clone();
+        board.make_move(m);
+
+        let reply = self.tt.get(board.hash())?.best_move;
+        board
+            .get_all_moves()
+            .into_iter()
+            .find(|legal| *legal == reply)
     }
 
     pub fn get_best_move(&mut self, depth: u32) -> Move {
-        let mut best_move_value = -9999;
-        let mut best_move = Move::null();
+        self.search(depth).0
+    }
 
-        for m in self.get_all_moves() {
-            let fen = self.board.to_fen();
-            self.board.make_move(m); // Changing the actual board state
+    /// Starts a new game from `board`.
+    pub fn set_position(&mut self, board: Board) {
+        self.start_fen = board.to_fen();
+        self.board = board;
+    }
 
-            let move_value = -self.negamax(depth - 1, -10000, 10000);
+    /// The position of the current game.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
 
-            self.board.undo_move(m);
+    /// Plays `m` in the current game.
+    pub fn make_move(&mut self, m: Move) {
+        self.board.make_move(m);
+    }
 
-            if move_value > best_move_value {
-                best_move_value = move_value;
-                best_move = m;
-            }
+    /// Plays a game against a human in the terminal, starting from
+    /// `board`. Returns the PGN result.
+    pub fn console(&mut self, board: Board, options: ConsoleOptions) -> &'static str {
+        Console::new(self, board, options).run()
+    }
+
+    /// Handles `bench` with a depth: prints the node signature and speed of
+    /// the bench positions, see `bench`.
+    pub fn bench(&self, depth: u32) {
+        print_bench(self, depth);
+    }
+
+    /// The game played since the last `set_position`, with the engine
+    /// playing both sides unless tags say otherwise.
+    pub fn to_pgn(&self) -> PgnGame {
+        let start = Board::from_fen(&self.start_fen).unwrap();
+        let mut game =
+            PgnGame::from_moves(&start, &self.board.moves_played(), result_of(&self.board));
+
+        game.set_tag("Event", "ChessEngine game");
+        game.set_tag("White", "ChessEngine");
+        game.set_tag("Black", "ChessEngine");
+        game
+    }
+
+    /// Prints the game as PGN, or saves it when `path` is given.
+    fn export_pgn(&self, path: Option<&str>) {
+        let pgn = self.to_pgn().to_string();
+
+        match path {
+            Some(path) => match std::fs::write(path, pgn) {
+                Ok(()) => println!("Game saved to {}", path),
+                Err(e) => println!("{}: {}", path, e),
+            },
+            None => print!("{}", pgn),
         }
-        best_move
     }
 
     pub fn run(&mut self) {
@@ -112,9 +407,106 @@ impl Engine {
             self.run_uci();
         } else if input == "play" {
             self.play();
+        } else if input == "console" {
+            Console::new(self, Board::new_game(), ConsoleOptions::default()).run();
+        } else if input == "eval" {
+            println!("{}", self.board.trace());
+        } else if input.starts_with("tune") {
+            if let Err(e) = self.tune(input) {
+                println!("{}", e);
+            }
+        } else if input.starts_with("datagen") {
+            if let Err(e) = self.datagen(input) {
+                println!("{}", e);
+            }
+        } else if input.starts_with("epd") {
+            if let Err(e) = self.epd(input) {
+                println!("{}", e);
+            }
+        } else if input.starts_with("bench") {
+            run_bench(self, input);
         }
     }
 
+    /// Handles `epd <suite> [depth] [movetime]`: runs a test suite, see
+    /// `epd`, searching each position to `depth` or for `movetime`
+    /// milliseconds, whichever ends first. A depth of 0 only limits time.
+    pub fn epd(&mut self, input: &str) -> Result<(), String> {
+        let mut args = input.split_whitespace().skip(1);
+
+        let path = args.next().ok_or("Usage: epd <suite> [depth] [movetime]")?;
+        let depth = args.next().and_then(|d| d.parse().ok()).unwrap_or(5);
+        let movetime = args.next().and_then(|t| t.parse().ok());
+
+        let limits = SearchLimits {
+            depth: Some(depth).filter(|&depth| depth > 0),
+            movetime: movetime.map(Duration::from_millis),
+            ..SearchLimits::default()
+        };
+        if limits.depth.is_none() && limits.movetime.is_none() {
+            return Err("epd needs a depth or a movetime".to_string());
+        }
+
+        run_suite(self, &load_epd(path)?, &limits);
+
+        Ok(())
+    }
+
+    /// Handles `datagen <output> [games] [depth] [nodes]`: self-play games
+    /// whose positions are written in the format documented in `datagen`.
+    pub fn datagen(&mut self, input: &str) -> Result<(), String> {
+        let mut args = input.split_whitespace().skip(1);
+
+        let output = args
+            .next()
+            .ok_or("Usage: datagen <output> [games] [depth] [nodes]")?;
+
+        let mut options = DatagenOptions::default();
+        if let Some(games) = args.next().and_then(|g| g.parse().ok()) {
+            options.games = games;
+        }
+        if let Some(depth) = args.next().and_then(|d| d.parse().ok()) {
+            options.depth = depth;
+        }
+        if let Some(nodes) = args.next().and_then(|n| n.parse().ok()) {
+            options.nodes = Some(nodes);
+        }
+
+        let positions = std::fs::File::create(output)
+            .and_then(|file| {
+                let mut writer = std::io::BufWriter::new(file);
+                generate(&options, &mut writer, |report| {
+                    eprintln!(
+                        "Game {}/{}: result {:.1}, {} positions",
+                        report.game, options.games, report.result, report.positions
+                    );
+                })
+            })
+            .map_err(|e| format!("{}: {}", output, e))?;
+
+        println!("{} positions written to {}", positions, output);
+
+        Ok(())
+    }
+
+    /// Handles `tune <positions> [output] [passes]`: Texel tuning of the
+    /// evaluation parameters, starting from the defaults, on a file of FENs
+    /// followed by game results.
+    pub fn tune(&mut self, input: &str) -> Result<(), String> {
+        let mut args = input.split_whitespace().skip(1);
+
+        let positions = args
+            .next()
+            .ok_or("Usage: tune <positions> [output] [passes]")?;
+        let output = args.next().unwrap_or("params.txt");
+        let passes = args.next().and_then(|p| p.parse().ok()).unwrap_or(100);
+
+        Tuner::new(load_positions(positions)?, EvalParams::default()).run(passes, output)?;
+        println!("Parameters saved to {}", output);
+
+        Ok(())
+    }
+
     pub fn play(&mut self) {
         let depth = 3;
 
@@ -136,71 +528,537 @@ impl Engine {
             self.board.make_move(m);
             println!("{}", self.board);
         }
+
+        self.export_pgn(None);
     }
 
     pub fn run_uci(&mut self) {
         println!("id name ChessEngine");
         println!("id author TriForMine");
+        println!(
+            "option name Threads type spin default 1 min 1 max {}",
+            MAX_THREADS
+        );
+        println!(
+            "option name MultiPV type spin default 1 min 1 max {}",
+            MAX_MULTIPV
+        );
+        println!("option name Evaluator type combo default Classic var Classic var Material");
+        println!("option name Ponder type check default false");
+        println!("option name EvalFile type string default <empty>");
+        println!("option name NNUEFile type string default <empty>");
         println!("uciok");
 
+        self.uci = true;
+
+        // Commands are read on their own thread so that `stop` can reach a
+        // running search
+        let (sender, mut receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in std::io::stdin().lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut pending = VecDeque::new();
+
         loop {
-            let mut input = String::new();
-            std::io::stdin().read_line(&mut input).unwrap();
+            let input = match pending.pop_front() {
+                Some(input) => input,
+                None => match receiver.recv() {
+                    Ok(input) => input,
+                    Err(_) => break,
+                },
+            };
 
             let input = input.trim();
 
             if input == "isready" {
                 println!("readyok");
             } else if input == "ucinewgame" {
-                self.board = Board::new_game();
+                self.set_position(Board::new_game());
+                self.clear_hash();
+            } else if input == "eval" {
+                println!("{}", self.board.trace());
+            } else if input.starts_with("bench") {
+                run_bench(self, input);
+            } else if input == "pgn" || input.starts_with("pgn ") {
+                self.export_pgn(input.split_whitespace().nth(1));
+            } else if input.starts_with("setoption") {
+                self.set_option(input);
             } else if input == "quit" {
                 break;
             } else if input.starts_with("position") {
-                let mut input = input.split_whitespace();
+                if let Err(e) = self.set_uci_position(input) {
+                    println!("info string {}", e);
+                }
+            } else if input.starts_with("go") {
+                // A malformed command still gets its bestmove, from a
+                // shallow search
+                let limits = SearchLimits::from_go(input, &self.board).unwrap_or_else(|e| {
+                    println!("info string {}", e);
+                    SearchLimits::depth(1)
+                });
 
-                input.next();
+                if self.go_uci(&limits, &mut receiver, &mut pending) {
+                    break;
+                }
+            }
+        }
+    }
 
-                if let Some(pos) = input.next() {
-                    if pos == "startpos" {
-                        self.board = Board::new_game();
-                    } else {
-                        let mut fen = String::new();
+    /// Handles `position [startpos | fen <fen>] [moves <moves>]`. Moves are
+    /// played up to the first illegal one, which is the error.
+    fn set_uci_position(&mut self, input: &str) -> Result<(), String> {
+        let tokens: Vec<&str> = input.split_whitespace().skip(1).collect();
+        let (position, moves) = match tokens.iter().position(|token| *token == "moves") {
+            Some(index) => (&tokens[..index], &tokens[index + 1..]),
+            None => (&tokens[..], &[][..]),
+        };
 
-                        for _ in 0..6 {
-                            fen.push_str(&format!("{} ", input.next().unwrap()));
-                        }
+        let board = match position {
+            ["startpos"] => Board::new_game(),
+            ["fen", fen @ ..] if !fen.is_empty() => {
+                let fen = fen.join(" ");
+                Board::from_fen(&fen).ok_or_else(|| format!("invalid FEN: {}", fen))?
+            }
+            _ => return Err(format!("invalid position command: {}", input)),
+        };
+        self.set_position(board);
 
-                        fen.pop();
+        for m in moves {
+            let m = self.board.parse_uci(m)?;
+            self.board.make_move(m);
+        }
 
-                        self.board.load_fen(&fen);
-                    }
-                }
+        Ok(())
+    }
+
+    /// Runs the search of a `go` command, answering `isready` and handling
+    /// `stop` and `quit` meanwhile. Other commands wait in `pending` until
+    /// the search is over. Returns true when asked to quit.
+    fn go_uci(
+        &mut self,
+        limits: &SearchLimits,
+        receiver: &mut Receiver<String>,
+        pending: &mut VecDeque<String>,
+    ) -> bool {
+        let (stop, pondering) = (self.stop.clone(), self.pondering.clone());
+        let finished = AtomicBool::new(false);
+        let mut quit = false;
+
+        self.start_search(limits);
 
-                if let Some(m) = input.next() {
-                    if m == "moves" {
-                        while let Some(m) = input.next() {
-                            let m = Move::from_str(m);
-                            self.board.make_move(m);
+        let result = std::thread::scope(|scope| {
+            let (finished, quit) = (&finished, &mut quit);
+
+            scope.spawn(move || {
+                while !finished.load(Ordering::Relaxed) {
+                    let input = match receiver.recv_timeout(Duration::from_millis(1)) {
+                        Ok(input) => input,
+                        Err(RecvTimeoutError::Timeout) => continue,
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    };
+
+                    match input.trim() {
+                        "isready" => println!("readyok"),
+                        "stop" => stop.store(true, Ordering::Relaxed),
+                        "ponderhit" => pondering.store(false, Ordering::Relaxed),
+                        "quit" => {
+                            stop.store(true, Ordering::Relaxed);
+                            *quit = true;
                         }
+                        _ => pending.push_back(input),
                     }
                 }
-            } else if input.starts_with("go") {
-                let mut input = input.split_whitespace();
+            });
 
-                input.next();
+            let result = self.run_search(limits, Some(&mut UciInfo));
+            finished.store(true, Ordering::Relaxed);
+            result
+        });
 
-                let mut depth = 3;
+        match (result.best_move, result.ponder_move) {
+            (m, _) if m == Move::null() => println!("bestmove 0000"),
+            (m, Some(reply)) => println!("bestmove {} ponder {}", m.to_str(), reply.to_str()),
+            (m, None) => println!("bestmove {}", m.to_str()),
+        }
 
-                while let Some(arg) = input.next() {
-                    if arg == "depth" {
-                        depth = input.next().unwrap().parse().unwrap();
-                    }
-                }
+        quit
+    }
 
-                let m = self.get_best_move(depth);
+    /// Handles `setoption name <name> value <value>`. Both the name and the
+    /// value may contain spaces.
+    fn set_option(&mut self, input: &str) {
+        let mut name = Vec::new();
+        let mut value = Vec::new();
+        let mut current = None;
 
-                println!("bestmove {}", m.to_str());
+        for token in input.split_whitespace().skip(1) {
+            if token == "name" {
+                current = Some(&mut name);
+            } else if token == "value" {
+                current = Some(&mut value);
+            } else if let Some(current) = current.as_mut() {
+                current.push(token);
+            }
+        }
+
+        let name = name.join(" ");
+        let value = value.join(" ");
+
+        if name == "Threads" {
+            if let Ok(threads) = value.parse() {
+                self.set_threads(threads);
+            }
+        } else if name == "MultiPV" {
+            if let Ok(multipv) = value.parse() {
+                self.set_multipv(multipv);
+            }
+        } else if name == "Evaluator" {
+            if let Some(evaluator) = evaluator_from_name(&value) {
+                self.set_evaluator(evaluator);
+            }
+        } else if name == "EvalFile" {
+            if value.is_empty() || value == "<empty>" {
+                return;
             }
+
+            match self.load_eval_file(&value) {
+                Ok(()) => println!("info string loaded evaluation parameters from {}", value),
+                Err(e) => println!("info string {}", e),
+            }
+        } else if name == "NNUEFile" {
+            if value.is_empty() || value == "<empty>" {
+                return;
+            }
+
+            match self.load_network(&value) {
+                Ok(()) => println!("info string loaded network from {}", value),
+                Err(e) => println!("info string {}", e),
+            }
+        }
+    }
+
+    /// Switches to a `TunedEvaluator` with the parameters saved in `path`.
+    pub fn load_eval_file(&mut self, path: &str) -> Result<(), String> {
+        let params = EvalParams::load(path)?;
+        self.set_evaluator(Box::new(TunedEvaluator::with_params(params)));
+
+        Ok(())
+    }
+
+    /// Switches to an `NnueEvaluator` with the network saved in `path`.
+    pub fn load_network(&mut self, path: &str) -> Result<(), String> {
+        let network = Network::load(path)?;
+        self.set_evaluator(Box::new(NnueEvaluator::new(Arc::new(network))));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::MaterialEvaluator;
+    use crate::search::{Bound, SearchInfo, NODE_FLUSH_INTERVAL};
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_mate_in_one() {
+        let mut engine = Engine::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w");
+
+        assert_eq!(engine.get_best_move(2).to_str(), "a1a8");
+    }
+
+    #[test]
+    fn test_search_result_and_callback() {
+        let mut engine = Engine::new();
+        let mut depths = Vec::new();
+        let mut callback = |info: &SearchInfo| {
+            if info.bound == Bound::Exact {
+                depths.push(info.depth);
+            }
+        };
+
+        let result = engine.go_with(&SearchLimits::depth(4), &mut callback);
+        assert_eq!(depths, vec![1, 2, 3, 4]);
+        assert_eq!(result.depth, 4);
+        assert_eq!(result.best_move, result.pv[0]);
+        assert_eq!(result.ponder_move, result.pv.get(1).copied());
+        assert!(result.nodes > 0);
+
+        // The principal variation is a legal line
+        let mut board = Board::new_game();
+        for m in result.pv {
+            assert!(board.get_all_moves().contains(&m));
+            board.make_move(m);
+        }
+    }
+
+    #[test]
+    fn test_multipv() {
+        let mut engine = Engine::new();
+        engine.set_multipv(3);
+
+        let result = engine.go(&SearchLimits::depth(3));
+        assert_eq!(result.lines.len(), 3);
+        assert_eq!(result.lines[0].pv, result.pv);
+        assert!(result.lines.windows(2).all(|w| w[0].score >= w[1].score));
+
+        let first_moves: HashSet<Move> = result.lines.iter().map(|line| line.pv[0]).collect();
+        assert_eq!(first_moves.len(), 3);
+
+        // No more lines than legal moves
+        let mut engine = Engine::from_fen("4k3/8/8/8/8/8/4r3/4K3 w");
+        engine.set_multipv(5);
+        assert_eq!(engine.go(&SearchLimits::depth(2)).lines.len(), 3);
+    }
+
+    #[test]
+    fn test_search_limits() {
+        let mut engine = Engine::new();
+
+        let result = engine.go(&SearchLimits::nodes(2000));
+        assert!(result.nodes < 4000);
+        assert_ne!(result.best_move, Move::null());
+
+        let limits = SearchLimits {
+            depth: Some(3),
+            searchmoves: vec![Move::from_str("a2a3")],
+            ..SearchLimits::default()
+        };
+        assert_eq!(engine.go(&limits).best_move.to_str(), "a2a3");
+
+        let start = Instant::now();
+        engine.go(&SearchLimits::movetime(Duration::from_millis(50)));
+        assert!(start.elapsed() < Duration::from_secs(2));
+
+        let mut engine = Engine::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w");
+        let limits = SearchLimits {
+            mate: Some(1),
+            ..SearchLimits::default()
+        };
+        let result = engine.go(&limits);
+        assert_eq!(result.best_move.to_str(), "a1a8");
+        assert_eq!(result.depth, 1);
+    }
+
+    #[test]
+    fn test_uci_position() {
+        let mut engine = Engine::new();
+
+        assert_eq!(
+            engine.set_uci_position("position startpos moves e2e4 e7e5"),
+            Ok(())
+        );
+        assert_eq!(engine.board.moves_played().len(), 2);
+
+        assert_eq!(
+            engine.set_uci_position("position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1 moves a1a8"),
+            Ok(())
+        );
+        assert!(engine.board.is_checkmate(false));
+
+        // Moves up to the illegal one are played
+        assert!(engine
+            .set_uci_position("position startpos moves e2e4 e2e4")
+            .is_err());
+        assert_eq!(engine.board.moves_played().len(), 1);
+
+        assert!(engine.set_uci_position("position fen").is_err());
+        assert!(engine.set_uci_position("position moves e2e4").is_err());
+    }
+
+    #[test]
+    fn test_node_limit_with_threads() {
+        let mut engine = Engine::new();
+        engine.set_threads(4);
+
+        let result = engine.go(&SearchLimits::nodes(20_000));
+        assert!(result.nodes >= 20_000);
+        assert!(result.nodes < 20_000 + 4 * NODE_FLUSH_INTERVAL);
+    }
+
+    #[test]
+    fn test_stopped_search_plays_a_searched_move() {
+        let mut engine = Engine::from_fen("4k3/8/8/3r4/8/3Q4/8/4K3 b");
+        let limits = SearchLimits {
+            btime: Some(Duration::from_millis(10)),
+            mate: Some(u32::MAX),
+            ..SearchLimits::default()
+        };
+
+        let result = engine.go(&limits);
+        assert_eq!(result.best_move.to_str(), "d5d3");
+        assert!(result.depth >= 1);
+    }
+
+    #[test]
+    fn test_ponderhit_starts_the_clock() {
+        let mut engine = Engine::new();
+        let pondering = engine.pondering_flag();
+        let limits = SearchLimits {
+            movetime: Some(Duration::from_millis(50)),
+            ponder: true,
+            ..SearchLimits::default()
+        };
+
+        let ponderhit = Mutex::new(None);
+        let result = std::thread::scope(|scope| {
+            scope.spawn(|| {
+                while !pondering.load(Ordering::Relaxed) {
+                    std::thread::yield_now();
+                }
+                *ponderhit.lock().unwrap() = Some(Instant::now());
+                pondering.store(false, Ordering::Relaxed);
+            });
+
+            engine.go(&limits)
+        });
+
+        // The search never ends by itself from the start position, so only
+        // the movetime counted from the ponderhit stops it
+        let ponderhit = ponderhit.into_inner().unwrap().unwrap();
+        assert!(ponderhit.elapsed() >= Duration::from_millis(50));
+        assert!(result.ponder_move.is_some());
+    }
+
+    #[test]
+    fn test_ponder_waits_for_ponderhit() {
+        let mut engine = Engine::new();
+        let pondering = engine.pondering_flag();
+        let limits = SearchLimits {
+            depth: Some(1),
+            ponder: true,
+            ..SearchLimits::default()
+        };
+
+        let ponderhit = AtomicBool::new(false);
+        let result = std::thread::scope(|scope| {
+            scope.spawn(|| {
+                while !pondering.load(Ordering::Relaxed) {
+                    std::thread::yield_now();
+                }
+                // Usually lets the depth 1 search finish before the
+                // ponderhit, which the assertions do not depend on
+                std::thread::sleep(Duration::from_millis(20));
+                ponderhit.store(true, Ordering::Relaxed);
+                pondering.store(false, Ordering::Relaxed);
+            });
+
+            let result = engine.go(&limits);
+            // Read before the scope waits for the thread sending ponderhit
+            assert!(ponderhit.load(Ordering::Relaxed));
+            result
+        });
+
+        assert_ne!(result.best_move, Move::null());
+    }
+
+    #[test]
+    fn test_ponder_with_clock_waits_for_ponderhit() {
+        let limits = SearchLimits {
+            depth: Some(2),
+            wtime: Some(Duration::from_secs(100)),
+            btime: Some(Duration::from_secs(100)),
+            ponder: true,
+            ..SearchLimits::default()
+        };
+
+        for threads in [1, 2] {
+            let mut engine = Engine::new();
+            engine.set_threads(threads);
+            let pondering = engine.pondering_flag();
+
+            let ponderhit = AtomicBool::new(false);
+            let result = std::thread::scope(|scope| {
+                scope.spawn(|| {
+                    while !pondering.load(Ordering::Relaxed) {
+                        std::thread::yield_now();
+                    }
+                    // The depth 2 search ends well before this, while the
+                    // timer thread still watches the clock
+                    std::thread::sleep(Duration::from_millis(100));
+                    ponderhit.store(true, Ordering::Relaxed);
+                    pondering.store(false, Ordering::Relaxed);
+                });
+
+                let result = engine.go(&limits);
+                // Read before the scope waits for the thread sending ponderhit
+                assert!(ponderhit.load(Ordering::Relaxed));
+                result
+            });
+
+            assert_ne!(result.best_move, Move::null());
         }
     }
+
+    #[test]
+    fn test_check_extension_raises_seldepth() {
+        let mut engine = Engine::from_fen("6k1/8/8/8/8/8/8/R5K1 w");
+
+        engine.get_best_move(1);
+        assert!(engine.seldepth() > 1);
+    }
+
+    #[test]
+    fn test_single_thread_is_deterministic() {
+        let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w";
+
+        let mut first = Engine::from_fen(fen);
+        let mut second = Engine::from_fen(fen);
+
+        assert_eq!(first.get_best_move(3), second.get_best_move(3));
+        assert_eq!(first.nodes(), second.nodes());
+    }
+
+    #[test]
+    fn test_custom_evaluator() {
+        let mut engine = Engine::from_fen("4k3/8/8/3r4/8/3Q4/8/4K3 b");
+        engine.set_evaluator(Box::new(MaterialEvaluator));
+
+        assert_eq!(engine.evaluator().name(), "Material");
+        assert_eq!(engine.get_best_move(2).to_str(), "d5d3");
+    }
+
+    #[test]
+    fn test_multi_threaded_search() {
+        let mut engine = Engine::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w");
+        engine.set_threads(4);
+
+        assert_eq!(engine.get_best_move(2).to_str(), "a1a8");
+    }
+
+    #[test]
+    fn test_pgn_export() {
+        let mut engine = Engine::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w");
+        let m = engine.get_best_move(2);
+        engine.board.make_move(m);
+
+        let pgn = engine.to_pgn().to_string();
+        assert!(pgn.contains("[Result \"1-0\"]"));
+        assert!(pgn.ends_with("1. Ra8# 1-0\n"));
+    }
+
+    #[test]
+    fn test_eval_file_option() {
+        // Named after the process, parallel test runs must not share it
+        let path = std::env::temp_dir().join(format!(
+            "chess_engine_test_params_{}.txt",
+            std::process::id()
+        ));
+        let path = path.to_str().unwrap();
+        EvalParams::default().save(path).unwrap();
+
+        let mut engine = Engine::new();
+        engine.set_option(&format!("setoption name EvalFile value {}", path));
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(engine.evaluator().name(), "Tuned");
+        assert!(engine.load_eval_file("/nonexistent/params.txt").is_err());
+    }
 }
@@ -0,0 +1,316 @@
+//! Extended Position Description, the format of test suites such as WAC.
+//!
+//! A line holds the piece placement, side to move, castling and en passant
+//! fields of a FEN, followed by operations: an opcode and its operands,
+//! each operation ending with `;`. Operands with spaces are quoted.
+
+use crate::board::Board;
+use crate::engine::Engine;
+use crate::limits::SearchLimits;
+use crate::r#move::Move;
+use std::fmt::Display;
+
+/// Opcodes whose operand is a free text string, written quoted.
+const STRING_OPCODES: [&str; 11] = [
+    "id", "c0", "c1", "c2", "c3", "c4", "c5", "c6", "c7", "c8", "c9",
+];
+
+#[derive(Clone)]
+pub struct Epd {
+    pub board: Board,
+    pub operations: Vec<(String, Vec<String>)>,
+}
+
+/// Operands of one operation, with quotes removed from quoted strings.
+fn split_operands(text: &str) -> Vec<String> {
+    let mut operands = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+
+    for c in text.chars() {
+        match c {
+            '"' => {
+                if quoted {
+                    operands.push(std::mem::take(&mut current));
+                }
+                quoted = !quoted;
+            }
+            _ if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    operands.push(std::mem::take(&mut current));
+                }
+            }
+            _ => current.push(c),
+        }
+    }
+
+    if !current.is_empty() {
+        operands.push(current);
+    }
+
+    operands
+}
+
+impl Epd {
+    pub fn get(&self, opcode: &str) -> Option<&[String]> {
+        self.operations
+            .iter()
+            .find(|(op, _)| op == opcode)
+            .map(|(_, operands)| operands.as_slice())
+    }
+
+    pub fn set(&mut self, opcode: &str, operands: Vec<String>) {
+        match self.operations.iter_mut().find(|(op, _)| op == opcode) {
+            Some((_, current)) => *current = operands,
+            None => self.operations.push((opcode.to_string(), operands)),
+        }
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.get("id")
+            .and_then(|operands| operands.first())
+            .map(String::as_str)
+    }
+
+    /// The SAN moves of `opcode`, such as `bm` or `am`, resolved on the
+    /// position.
+    pub fn moves(&self, opcode: &str) -> Result<Vec<Move>, String> {
+        self.get(opcode)
+            .unwrap_or_default()
+            .iter()
+            .map(|san| self.board.parse_san(san))
+            .collect()
+    }
+
+    /// The `bm` and `am` moves resolved on the position, failing if any of
+    /// them is not a legal move.
+    pub(crate) fn solution(&self) -> Result<Solution, String> {
+        Ok(Solution {
+            best: self.moves("bm")?,
+            avoid: self.moves("am")?,
+        })
+    }
+
+    /// Whether `m` solves the position, see `Solution::is_solved_by`.
+    pub fn is_solved_by(&self, m: Move) -> Result<bool, String> {
+        Ok(self.solution()?.is_solved_by(m))
+    }
+}
+
+/// The moves a test position expects, as resolved by `Epd::solution`.
+pub(crate) struct Solution {
+    best: Vec<Move>,
+    avoid: Vec<Move>,
+}
+
+impl Solution {
+    /// Whether `m` is one of the best moves, and not one of the moves to
+    /// avoid.
+    pub(crate) fn is_solved_by(&self, m: Move) -> bool {
+        (self.best.is_empty() || self.best.contains(&m)) && !self.avoid.contains(&m)
+    }
+}
+
+impl Display for Epd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.board.to_epd())?;
+
+        for (opcode, operands) in &self.operations {
+            write!(f, " {}", opcode)?;
+
+            for operand in operands {
+                if STRING_OPCODES.contains(&opcode.as_str()) || operand.contains(' ') {
+                    write!(f, " \"{}\"", operand)?;
+                } else {
+                    write!(f, " {}", operand)?;
+                }
+            }
+
+            write!(f, ";")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Board {
+    /// Parses an EPD line into the position and its operations.
+    pub fn from_epd(line: &str) -> Result<Epd, String> {
+        let mut rest = line.trim();
+        let mut fields = Vec::new();
+
+        for _ in 0..4 {
+            let (field, tail) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            if field.is_empty() {
+                return Err(format!("missing position fields in EPD: {}", line));
+            }
+
+            fields.push(field);
+            rest = tail.trim_start();
+        }
+
+        let board = Board::from_fen(&fields.join(" "))
+            .ok_or_else(|| format!("invalid position in EPD: {}", line))?;
+
+        // Split on `;` outside of quoted strings
+        let mut operations = Vec::new();
+        let mut current = String::new();
+        let mut quoted = false;
+
+        for c in rest.chars() {
+            if c == '"' {
+                quoted = !quoted;
+            }
+
+            if c == ';' && !quoted {
+                let mut operands = split_operands(&current);
+                if !operands.is_empty() {
+                    let opcode = operands.remove(0);
+                    operations.push((opcode, operands));
+                }
+                current.clear();
+            } else {
+                current.push(c);
+            }
+        }
+
+        if !current.trim().is_empty() {
+            return Err(format!("unterminated operation '{}'", current.trim()));
+        }
+
+        Ok(Epd { board, operations })
+    }
+
+    /// The four position fields of EPD. Castling and en passant are not
+    /// supported, so they are always `-`.
+    pub fn to_epd(&self) -> String {
+        format!("{} - -", self.to_fen())
+    }
+}
+
+pub fn load_epd(path: &str) -> Result<Vec<Epd>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(Board::from_epd)
+        .collect()
+}
+
+/// Outcome of `run_suite`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SuiteResult {
+    pub solved: usize,
+    pub failed: usize,
+    /// Positions whose `bm` or `am` moves could not be resolved, which are
+    /// not searched.
+    pub skipped: usize,
+}
+
+/// Searches every position of `suite` within `limits`, typically a fixed
+/// depth or time, printing one line per position and a summary.
+pub fn run_suite(engine: &mut Engine, suite: &[Epd], limits: &SearchLimits) -> SuiteResult {
+    let mut outcome = SuiteResult::default();
+
+    for (index, epd) in suite.iter().enumerate() {
+        let id = epd
+            .id()
+            .map(str::to_string)
+            .unwrap_or_else(|| (index + 1).to_string());
+
+        let solution = match epd.solution() {
+            Ok(solution) => solution,
+            Err(e) => {
+                println!("{:<12} {:<7} {}", id, "skipped", e);
+                outcome.skipped += 1;
+                continue;
+            }
+        };
+
+        engine.set_position(epd.board.clone());
+        engine.clear_hash();
+
+        let search = engine.go(limits);
+        let m = search.best_move;
+
+        let status = if solution.is_solved_by(m) {
+            outcome.solved += 1;
+            "solved"
+        } else {
+            outcome.failed += 1;
+            "failed"
+        };
+
+        let mut result = epd.clone();
+        result.set("acd", vec![search.depth.to_string()]);
+        result.set("ce", vec![search.score.to_string()]);
+        result.set("pm", vec![epd.board.move_to_san(m)]);
+
+        println!("{:<12} {:<7} {}", id, status, result);
+    }
+
+    print!(
+        "Solved {}/{}",
+        outcome.solved,
+        outcome.solved + outcome.failed
+    );
+    if outcome.skipped > 0 {
+        print!(", {} skipped", outcome.skipped);
+    }
+    println!();
+
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINE: &str =
+        "6k1/5ppp/8/8/8/8/8/R5K1 w - - bm Ra8#; am Ra7; id \"mate; in one\"; c0 \"back rank\";";
+
+    #[test]
+    fn test_parse_epd() {
+        let epd = Board::from_epd(LINE).unwrap();
+
+        assert_eq!(epd.board.to_fen(), "6k1/5ppp/8/8/8/8/8/R5K1 w");
+        assert_eq!(epd.id(), Some("mate; in one"));
+        assert_eq!(epd.get("c0"), Some(&["back rank".to_string()][..]));
+        assert_eq!(epd.moves("bm"), Ok(vec![Move::from_str("a1a8")]));
+        assert_eq!(epd.is_solved_by(Move::from_str("a1a8")), Ok(true));
+        assert_eq!(epd.is_solved_by(Move::from_str("a1a7")), Ok(false));
+
+        assert!(Board::from_epd("6k1/5ppp/8/8/8/8/8/R5K1 w").is_err());
+        assert!(Board::from_epd("6k1/5ppp/8/8/8/8/8/R5K1 w - - bm Ra8").is_err());
+    }
+
+    #[test]
+    fn test_emit_epd() {
+        let epd = Board::from_epd(LINE).unwrap();
+
+        assert_eq!(epd.to_string(), LINE);
+        assert_eq!(Board::from_epd(&epd.to_string()).unwrap().to_string(), LINE);
+    }
+
+    #[test]
+    fn test_run_suite() {
+        let suite = vec![
+            Board::from_epd(LINE).unwrap(),
+            Board::from_epd("4k3/8/8/3r4/8/3Q4/8/4K3 b - - bm Rxd3; id \"free queen\";").unwrap(),
+            Board::from_epd("4k3/8/8/3r4/8/3Q4/8/4K3 b - - bm Rxd1; id \"illegal\";").unwrap(),
+        ];
+
+        let expected = SuiteResult {
+            solved: 2,
+            failed: 0,
+            skipped: 1,
+        };
+        assert_eq!(
+            run_suite(&mut Engine::new(), &suite, &SearchLimits::depth(2)),
+            expected
+        );
+
+        let limits = SearchLimits::movetime(std::time::Duration::from_millis(50));
+        assert_eq!(run_suite(&mut Engine::new(), &suite, &limits), expected);
+    }
+}
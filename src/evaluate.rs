@@ -1,5 +1,36 @@
-use crate::board::Board;
-use crate::coord::Coord;
+/// Piece values in centipawns, indexed like `Piece::to_index`. Used for
+/// exchange evaluation, where the king must outweigh everything else.
+pub const PIECE_VALUES: [i16; 6] = [100, 320, 330, 500, 900, 20000];
+
+/// Material in the middlegame and the endgame, indexed like `Piece::to_index`.
+pub const MATERIAL_MG: [i16; 6] = [82, 337, 365, 477, 1025, 0];
+pub const MATERIAL_EG: [i16; 6] = [94, 281, 297, 512, 936, 0];
+
+/// How much each piece counts towards the game phase. The starting position
+/// has `MAX_PHASE`, bare kings and pawns have 0.
+pub const PHASE_WEIGHTS: [i16; 6] = [0, 1, 1, 2, 4, 0];
+pub const MAX_PHASE: i16 = 24;
+
+/// Blends a middlegame and an endgame score according to `phase`.
+pub fn taper(mg: i16, eg: i16, phase: i16) -> i16 {
+    let phase = phase.min(MAX_PHASE) as i32;
+
+    ((mg as i32 * phase + eg as i32 * (MAX_PHASE as i32 - phase)) / MAX_PHASE as i32) as i16
+}
+
+// Pawn structure terms as `[middlegame, endgame]`, the ones indexed by rank
+// use the rank relative to the pawn's side
+
+pub const PASSED_PAWN: [[i16; 8]; 2] = [
+    [0, 5, 10, 15, 25, 40, 60, 0],
+    [0, 10, 20, 35, 60, 100, 150, 0],
+];
+pub const CANDIDATE_PAWN: [[i16; 8]; 2] = [[0, 2, 3, 5, 10, 20, 0, 0], [0, 4, 6, 10, 20, 35, 0, 0]];
+pub const CONNECTED_PAWN: [[i16; 8]; 2] = [[0, 3, 4, 6, 10, 15, 25, 0], [0, 2, 3, 5, 8, 12, 20, 0]];
+pub const ISOLATED_PAWN: [i16; 2] = [-10, -15];
+pub const DOUBLED_PAWN: [i16; 2] = [-10, -20];
+pub const BACKWARD_PAWN: [i16; 2] = [-8, -10];
+pub const PAWN_ISLAND: [i16; 2] = [-5, -8];
 
 pub const PAWN_SCORE: [[i16; 64]; 2] = [
     [
@@ -118,36 +149,283 @@ pub const QUEEN_SCORE: [[i16; 64]; 2] = [
 
 pub const KING_SCORE: [[i16; 64]; 2] = [
     [
-        20, 30, 10, 0, 0, 10, 30, 20, // White
-        20, 20, 0, 0, 0, 0, 20, 20, // White
-        -10, -20, -20, -20, -20, -20, -20, -10, // White
-        -20, -30, -30, -40, -40, -30, -30, -20, // White
         -30, -40, -40, -50, -50, -40, -40, -30, // White
         -30, -40, -40, -50, -50, -40, -40, -30, // White
         -30, -40, -40, -50, -50, -40, -40, -30, // White
         -30, -40, -40, -50, -50, -40, -40, -30, // White
+        -20, -30, -30, -40, -40, -30, -30, -20, // White
+        -10, -20, -20, -20, -20, -20, -20, -10, // White
+        20, 20, 0, 0, 0, 0, 20, 20, // White
+        20, 30, 10, 0, 0, 10, 30, 20, // White
     ],
     [
+        20, 30, 10, 0, 0, 10, 30, 20, // Black
+        20, 20, 0, 0, 0, 0, 20, 20, // Black
+        -10, -20, -20, -20, -20, -20, -20, -10, // Black
+        -20, -30, -30, -40, -40, -30, -30, -20, // Black
         -30, -40, -40, -50, -50, -40, -40, -30, // Black
         -30, -40, -40, -50, -50, -40, -40, -30, // Black
         -30, -40, -40, -50, -50, -40, -40, -30, // Black
         -30, -40, -40, -50, -50, -40, -40, -30, // Black
-        -20, -30, -30, -40, -40, -30, -30, -20, // Black
-        -10, -20, -20, -20, -20, -20, -20, -10, // Black
-        20, 20, 0, 0, 0, 0, 20, 20, // Black
-        20, 30, 10, 0, 0, 10, 30, 20, // Black
     ],
 ];
 
+pub const PAWN_SCORE_EG: [[i16; 64]; 2] = [
+    [
+        0, 0, 0, 0, 0, 0, 0, 0, // White
+        80, 80, 80, 80, 80, 80, 80, 80, // White
+        50, 50, 50, 50, 50, 50, 50, 50, // White
+        30, 30, 30, 30, 30, 30, 30, 30, // White
+        20, 20, 20, 20, 20, 20, 20, 20, // White
+        10, 10, 10, 10, 10, 10, 10, 10, // White
+        10, 10, 10, 10, 10, 10, 10, 10, // White
+        0, 0, 0, 0, 0, 0, 0, 0, // White
+    ],
+    [
+        0, 0, 0, 0, 0, 0, 0, 0, // Black
+        10, 10, 10, 10, 10, 10, 10, 10, // Black
+        10, 10, 10, 10, 10, 10, 10, 10, // Black
+        20, 20, 20, 20, 20, 20, 20, 20, // Black
+        30, 30, 30, 30, 30, 30, 30, 30, // Black
+        50, 50, 50, 50, 50, 50, 50, 50, // Black
+        80, 80, 80, 80, 80, 80, 80, 80, // Black
+        0, 0, 0, 0, 0, 0, 0, 0, // Black
+    ],
+];
+
+pub const KNIGHT_SCORE_EG: [[i16; 64]; 2] = [
+    [
+        -50, -40, -30, -30, -30, -30, -40, -50, // White
+        -40, -20, 0, 0, 0, 0, -20, -40, // White
+        -30, 0, 10, 15, 15, 10, 0, -30, // White
+        -30, 5, 15, 20, 20, 15, 5, -30, // White
+        -30, 5, 15, 20, 20, 15, 5, -30, // White
+        -30, 0, 10, 15, 15, 10, 0, -30, // White
+        -40, -20, 0, 0, 0, 0, -20, -40, // White
+        -50, -40, -30, -30, -30, -30, -40, -50, // White
+    ],
+    [
+        -50, -40, -30, -30, -30, -30, -40, -50, // Black
+        -40, -20, 0, 0, 0, 0, -20, -40, // Black
+        -30, 0, 10, 15, 15, 10, 0, -30, // Black
+        -30, 5, 15, 20, 20, 15, 5, -30, // Black
+        -30, 5, 15, 20, 20, 15, 5, -30, // Black
+        -30, 0, 10, 15, 15, 10, 0, -30, // Black
+        -40, -20, 0, 0, 0, 0, -20, -40, // Black
+        -50, -40, -30, -30, -30, -30, -40, -50, // Black
+    ],
+];
+
+pub const BISHOP_SCORE_EG: [[i16; 64]; 2] = [
+    [
+        -20, -10, -10, -10, -10, -10, -10, -20, // White
+        -10, 0, 0, 0, 0, 0, 0, -10, // White
+        -10, 0, 5, 10, 10, 5, 0, -10, // White
+        -10, 0, 10, 15, 15, 10, 0, -10, // White
+        -10, 0, 10, 15, 15, 10, 0, -10, // White
+        -10, 0, 5, 10, 10, 5, 0, -10, // White
+        -10, 0, 0, 0, 0, 0, 0, -10, // White
+        -20, -10, -10, -10, -10, -10, -10, -20, // White
+    ],
+    [
+        -20, -10, -10, -10, -10, -10, -10, -20, // Black
+        -10, 0, 0, 0, 0, 0, 0, -10, // Black
+        -10, 0, 5, 10, 10, 5, 0, -10, // Black
+        -10, 0, 10, 15, 15, 10, 0, -10, // Black
+        -10, 0, 10, 15, 15, 10, 0, -10, // Black
+        -10, 0, 5, 10, 10, 5, 0, -10, // Black
+        -10, 0, 0, 0, 0, 0, 0, -10, // Black
+        -20, -10, -10, -10, -10, -10, -10, -20, // Black
+    ],
+];
+
+pub const ROOK_SCORE_EG: [[i16; 64]; 2] = [
+    [
+        0, 0, 0, 0, 0, 0, 0, 0, // White
+        5, 5, 5, 5, 5, 5, 5, 5, // White
+        0, 0, 0, 0, 0, 0, 0, 0, // White
+        0, 0, 0, 0, 0, 0, 0, 0, // White
+        0, 0, 0, 0, 0, 0, 0, 0, // White
+        0, 0, 0, 0, 0, 0, 0, 0, // White
+        0, 0, 0, 0, 0, 0, 0, 0, // White
+        0, 0, 0, 0, 0, 0, 0, 0, // White
+    ],
+    [
+        0, 0, 0, 0, 0, 0, 0, 0, // Black
+        0, 0, 0, 0, 0, 0, 0, 0, // Black
+        0, 0, 0, 0, 0, 0, 0, 0, // Black
+        0, 0, 0, 0, 0, 0, 0, 0, // Black
+        0, 0, 0, 0, 0, 0, 0, 0, // Black
+        0, 0, 0, 0, 0, 0, 0, 0, // Black
+        5, 5, 5, 5, 5, 5, 5, 5, // Black
+        0, 0, 0, 0, 0, 0, 0, 0, // Black
+    ],
+];
+
+pub const QUEEN_SCORE_EG: [[i16; 64]; 2] = [
+    [
+        -20, -10, -10, -5, -5, -10, -10, -20, // White
+        -10, 0, 0, 0, 0, 0, 0, -10, // White
+        -10, 0, 5, 5, 5, 5, 0, -10, // White
+        -5, 0, 5, 10, 10, 5, 0, -5, // White
+        -5, 0, 5, 10, 10, 5, 0, -5, // White
+        -10, 0, 5, 5, 5, 5, 0, -10, // White
+        -10, 0, 0, 0, 0, 0, 0, -10, // White
+        -20, -10, -10, -5, -5, -10, -10, -20, // White
+    ],
+    [
+        -20, -10, -10, -5, -5, -10, -10, -20, // Black
+        -10, 0, 0, 0, 0, 0, 0, -10, // Black
+        -10, 0, 5, 5, 5, 5, 0, -10, // Black
+        -5, 0, 5, 10, 10, 5, 0, -5, // Black
+        -5, 0, 5, 10, 10, 5, 0, -5, // Black
+        -10, 0, 5, 5, 5, 5, 0, -10, // Black
+        -10, 0, 0, 0, 0, 0, 0, -10, // Black
+        -20, -10, -10, -5, -5, -10, -10, -20, // Black
+    ],
+];
+
+pub const KING_SCORE_EG: [[i16; 64]; 2] = [
+    [
+        -50, -30, -30, -30, -30, -30, -30, -50, // White
+        -30, -30, 0, 0, 0, 0, -30, -30, // White
+        -30, -10, 20, 30, 30, 20, -10, -30, // White
+        -30, -10, 30, 40, 40, 30, -10, -30, // White
+        -30, -10, 30, 40, 40, 30, -10, -30, // White
+        -30, -10, 20, 30, 30, 20, -10, -30, // White
+        -30, -30, 0, 0, 0, 0, -30, -30, // White
+        -50, -30, -30, -30, -30, -30, -30, -50, // White
+    ],
+    [
+        -50, -30, -30, -30, -30, -30, -30, -50, // Black
+        -30, -30, 0, 0, 0, 0, -30, -30, // Black
+        -30, -10, 20, 30, 30, 20, -10, -30, // Black
+        -30, -10, 30, 40, 40, 30, -10, -30, // Black
+        -30, -10, 30, 40, 40, 30, -10, -30, // Black
+        -30, -10, 20, 30, 30, 20, -10, -30, // Black
+        -30, -30, 0, 0, 0, 0, -30, -30, // Black
+        -50, -30, -30, -30, -30, -30, -30, -50, // Black
+    ],
+];
+
+// King safety terms as `[middlegame, endgame]`. Shield and storm penalties
+// are indexed by how many ranks in front of the king the pawn stands.
+
+pub const PAWN_SHIELD: [[i16; 4]; 2] = [[-25, 10, 5, 0], [0, 0, 0, 0]];
+pub const PAWN_STORM: [[i16; 4]; 2] = [[0, -30, -20, -10], [0, -5, -3, 0]];
+pub const SEMI_OPEN_FILE_NEAR_KING: [i16; 2] = [-15, 0];
+pub const OPEN_FILE_NEAR_KING: [i16; 2] = [-25, -5];
+
+/// Attack units per square of the king zone hit, indexed like
+/// `Piece::to_index`.
+pub const KING_ATTACK_WEIGHTS: [i16; 6] = [0, 2, 2, 3, 5, 0];
+
+/// Middlegame penalty by attack units, once two or more pieces attack the
+/// king zone. The endgame penalty is a quarter of it.
+pub const KING_DANGER: [i16; 64] = [
+    0, 0, 1, 2, 3, 5, 7, 9, 12, 15, 18, 22, 26, 30, 35, 39, 44, 50, 56, 62, 68, 75, 82, 85, 89, 97,
+    105, 113, 122, 131, 140, 150, 169, 180, 191, 202, 213, 225, 237, 248, 260, 272, 283, 295, 307,
+    319, 330, 342, 354, 366, 377, 389, 401, 412, 424, 436, 448, 459, 471, 483, 494, 500, 500, 500,
+];
+
+// Mobility and piece activity terms as `[middlegame, endgame]`. Mobility
+// counts the squares a piece attacks that hold no own piece and are not
+// attacked by enemy pawns, minus the baseline, indexed like
+// `Piece::to_index`.
+
+pub const MOBILITY: [[i16; 6]; 2] = [[0, 4, 5, 2, 1, 0], [0, 4, 5, 4, 2, 0]];
+pub const MOBILITY_BASELINE: [i16; 6] = [0, 4, 6, 7, 13, 0];
+pub const BISHOP_PAIR: [i16; 2] = [30, 50];
+pub const KNIGHT_OUTPOST: [i16; 2] = [20, 10];
+pub const ROOK_ON_OPEN_FILE: [i16; 2] = [25, 10];
+pub const ROOK_ON_SEMI_OPEN_FILE: [i16; 2] = [10, 5];
+pub const ROOK_ON_SEVENTH: [i16; 2] = [20, 30];
+pub const TRAPPED_BISHOP: [i16; 2] = [-100, -100];
+pub const TRAPPED_ROOK: [i16; 2] = [-40, 0];
+
+/// Scale of each evaluation term in percent, in the order of
+/// `EvalTerm::ALL`: material, piece-square, pawns, king safety, mobility.
+/// The classic evaluation weighs them all equally; parameter files may
+/// change them for `TunedEvaluator`.
+pub const TERM_WEIGHTS: [i16; 5] = [100, 100, 100, 100, 100];
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::board::Board;
     use crate::r#move::Move;
 
     #[test]
     fn test_score() {
         let board = Board::new_game();
 
-        assert_eq!(board.evaluate(), 0);
+        assert_eq!(board.evaluate_white(), 0);
+    }
+
+    #[test]
+    fn test_material() {
+        let board = Board::from_fen("3qk3/8/8/8/8/8/8/4K3 w").unwrap();
+
+        assert!(board.evaluate_white() < -800);
+        assert_eq!(board.evaluate_relative(), board.evaluate_white());
+    }
+
+    /// Flips the board vertically and swaps the colors and the side to move.
+    fn mirror_fen(fen: &str) -> String {
+        let mut parts = fen.split_whitespace();
+        let ranks: Vec<String> = parts
+            .next()
+            .unwrap()
+            .split('/')
+            .rev()
+            .map(|rank| {
+                rank.chars()
+                    .map(|c| {
+                        if c.is_uppercase() {
+                            c.to_ascii_lowercase()
+                        } else {
+                            c.to_ascii_uppercase()
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let turn = if parts.next() == Some("w") { "b" } else { "w" };
+
+        format!("{} {}", ranks.join("/"), turn)
+    }
+
+    #[test]
+    fn test_mirrored_positions() {
+        for fen in [
+            "3qk3/8/8/8/8/8/8/4K3 w",
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b",
+            "8/5k2/3p4/1p1P4/1P6/5K2/8/8 w",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            let mirrored = Board::from_fen(&mirror_fen(fen)).unwrap();
+
+            assert_eq!(board.evaluate_relative(), mirrored.evaluate_relative());
+            assert_eq!(board.evaluate_white(), -mirrored.evaluate_white());
+        }
+    }
+
+    #[test]
+    fn test_taper() {
+        assert_eq!(taper(100, 20, MAX_PHASE), 100);
+        assert_eq!(taper(100, 20, 0), 20);
+        assert_eq!(taper(100, 20, MAX_PHASE / 2), 60);
+    }
+
+    #[test]
+    fn test_incremental_score() {
+        let mut board = Board::new_game();
+        for m in ["e2e4", "d7d5", "e4d5", "d8d5", "b1c3"] {
+            board.make_move(Move::from_str(m));
+        }
+
+        let fresh = Board::from_fen(&board.to_fen()).unwrap();
+        assert_eq!(board.evaluate_white(), fresh.evaluate_white());
+        assert_eq!(board.game_phase(), fresh.game_phase());
     }
 }
@@ -0,0 +1,162 @@
+use crate::bitboard::BitBoard;
+use crate::board::Board;
+use crate::cache::king::KING_MOVES;
+use crate::cache::piece_attacks;
+use crate::coord::Coord;
+use crate::params::EvalParams;
+
+/// Ranks between the king and the closest pawn in front of it on `file`,
+/// looking at most three ranks ahead. 0 if there is none.
+fn pawn_distance(pawns: BitBoard, king: Coord, file: u8, color: bool) -> usize {
+    for distance in 1..=3u8 {
+        let rank = if color {
+            king.y.checked_add(distance).filter(|rank| *rank < 8)
+        } else {
+            king.y.checked_sub(distance)
+        };
+
+        match rank {
+            Some(rank) if pawns.get(Coord::new(file, rank)) => return distance as usize,
+            Some(_) => {}
+            None => break,
+        }
+    }
+
+    0
+}
+
+/// Pawn shield, pawn storm and open files around `color`'s king.
+fn pawn_cover(board: &Board, king: Coord, color: bool, params: &EvalParams) -> (i16, i16) {
+    let own_pawns = board.get_piece_index_bitboard(0, color);
+    let enemy_pawns = board.get_piece_index_bitboard(0, !color);
+
+    let mut mg = 0;
+    let mut eg = 0;
+
+    for file in king.x.saturating_sub(1)..=(king.x + 1).min(7) {
+        let shield = pawn_distance(own_pawns, king, file, color);
+        let storm = pawn_distance(enemy_pawns, king, file, color);
+        mg += params.pawn_shield[0][shield] + params.pawn_storm[0][storm];
+        eg += params.pawn_shield[1][shield] + params.pawn_storm[1][storm];
+
+        let own_on_file = (0..8).any(|rank| own_pawns.get(Coord::new(file, rank)));
+        let enemy_on_file = (0..8).any(|rank| enemy_pawns.get(Coord::new(file, rank)));
+
+        if !own_on_file && !enemy_on_file {
+            mg += params.open_file_near_king[0];
+            eg += params.open_file_near_king[1];
+        } else if !own_on_file {
+            mg += params.semi_open_file_near_king[0];
+            eg += params.semi_open_file_near_king[1];
+        }
+    }
+
+    (mg, eg)
+}
+
+/// Penalty for the enemy pieces attacking the squares around `color`'s king.
+/// Each attacked zone square adds the attacker's weight in attack units,
+/// which only count once at least two pieces take part in the attack.
+fn king_attacks(board: &Board, king: Coord, color: bool, params: &EvalParams) -> (i16, i16) {
+    let zone = KING_MOVES[king.to_index() as usize] | BitBoard::from_coord(king);
+    let occupied = board.occupied_bitboard();
+
+    let mut attackers = 0;
+    // Signed, as weights loaded from a file or tuned may be negative
+    let mut units: i32 = 0;
+
+    for index in 1..5u8 {
+        let mut pieces = board.get_piece_index_bitboard(index, !color).get_board();
+
+        while pieces != 0 {
+            let coord = Coord::from_index(pieces.trailing_zeros() as u8);
+            pieces &= pieces - 1;
+
+            let hits = (piece_attacks(index, coord, occupied) & zone)
+                .get_board()
+                .count_ones();
+
+            if hits > 0 {
+                attackers += 1;
+                units += i32::from(params.king_attack_weights[index as usize]) * hits as i32;
+            }
+        }
+    }
+
+    if attackers < 2 {
+        return (0, 0);
+    }
+
+    let danger = params.king_danger[(units.max(0) as usize).min(params.king_danger.len() - 1)];
+    (-danger, -danger / 4)
+}
+
+/// King safety score of `color`'s king alone, as `(middlegame, endgame)`.
+pub fn evaluate_king_safety_side(board: &Board, color: bool, params: &EvalParams) -> (i16, i16) {
+    let king = match board.get_piece_index_coord(5, color) {
+        Some(king) => king,
+        None => return (0, 0),
+    };
+
+    let (cover_mg, cover_eg) = pawn_cover(board, king, color, params);
+    let (attack_mg, attack_eg) = king_attacks(board, king, color, params);
+
+    (cover_mg + attack_mg, cover_eg + attack_eg)
+}
+
+/// King safety score, white minus black, as `(middlegame, endgame)`.
+pub fn evaluate_king_safety(board: &Board, params: &EvalParams) -> (i16, i16) {
+    let (white_mg, white_eg) = evaluate_king_safety_side(board, true, params);
+    let (black_mg, black_eg) = evaluate_king_safety_side(board, false, params);
+
+    (white_mg - black_mg, white_eg - black_eg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::params::DEFAULT_PARAMS;
+
+    #[test]
+    fn test_symmetric_start() {
+        assert_eq!(
+            evaluate_king_safety(&Board::new_game(), &DEFAULT_PARAMS),
+            (0, 0)
+        );
+    }
+
+    #[test]
+    fn test_pawn_shield() {
+        let sheltered = Board::from_fen("6k1/5ppp/8/8/8/8/5PPP/6K1 w").unwrap();
+        let exposed = Board::from_fen("6k1/5ppp/8/8/8/5PPP/8/6K1 w").unwrap();
+
+        assert!(
+            evaluate_king_safety(&sheltered, &DEFAULT_PARAMS).0
+                > evaluate_king_safety(&exposed, &DEFAULT_PARAMS).0
+        );
+    }
+
+    #[test]
+    fn test_king_attack() {
+        let quiet = Board::from_fen("6k1/5ppp/8/8/8/8/5PPP/Q5K1 w").unwrap();
+        let attacked = Board::from_fen("6k1/5ppp/7Q/6N1/8/8/5PPP/6K1 w").unwrap();
+
+        assert!(
+            evaluate_king_safety(&attacked, &DEFAULT_PARAMS).0
+                > evaluate_king_safety(&quiet, &DEFAULT_PARAMS).0
+        );
+    }
+
+    #[test]
+    fn test_negative_attack_weights() {
+        let attacked = Board::from_fen("6k1/5ppp/7Q/6N1/8/8/5PPP/6K1 w").unwrap();
+        let mut params = DEFAULT_PARAMS.clone();
+        params.king_attack_weights = [-100; 6];
+
+        let quiet = Board::from_fen("6k1/5ppp/8/8/8/8/5PPP/6K1 w").unwrap();
+        assert_eq!(
+            evaluate_king_safety(&attacked, &params),
+            evaluate_king_safety(&quiet, &params)
+        );
+    }
+}
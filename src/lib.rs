@@ -0,0 +1,65 @@
+//! A chess engine: board representation and legal move generation, FEN,
+//! SAN, PGN and EPD support, hand-tuned and NNUE evaluations, and a
+//! multi-threaded alpha-beta search.
+//!
+//! ```no_run
+//! use chess_engine::{Board, Engine};
+//!
+//! let mut engine = Engine::new();
+//! engine.set_position(Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w").unwrap());
+//!
+//! let (m, score) = engine.search(4);
+//! println!("{} {}", engine.board().move_to_san(m), score);
+//! ```
+
+mod bench;
+mod bitboard;
+mod board;
+mod cache;
+mod console;
+mod coord;
+mod datagen;
+mod engine;
+mod epd;
+mod evaluate;
+mod evaluator;
+mod king_safety;
+mod limits;
+mod mobility;
+mod r#move;
+mod moves;
+mod nnue;
+mod params;
+mod pawns;
+mod perft;
+mod pgn;
+mod piece;
+mod san;
+mod search;
+mod trace;
+mod tt;
+mod tuner;
+mod zobrist;
+
+pub use bench::DEFAULT_BENCH_DEPTH;
+pub use bitboard::BitBoard;
+pub use board::{Board, MoveWithCapture, OneSideBoard};
+pub use console::ConsoleOptions;
+pub use coord::Coord;
+pub use datagen::{generate, DatagenOptions, GameReport};
+pub use engine::Engine;
+pub use epd::Epd;
+pub use evaluator::{
+    ClassicEvaluator, Evaluator, MaterialEvaluator, NnueEvaluator, TunedEvaluator,
+};
+pub use limits::SearchLimits;
+pub use nnue::Network;
+pub use params::EvalParams;
+pub use perft::{divide, perft};
+pub use pgn::{load_pgn, parse_pgn, PgnGame, PgnNode};
+pub use piece::{Piece, PieceEnum};
+pub use r#move::Move;
+pub use search::{
+    Bound, Extensions, InfoCallback, PvLine, SearchInfo, SearchResult, MATE_SCORE, MAX_PLY,
+};
+pub use trace::{EvalTerm, EvalTrace};
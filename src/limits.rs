@@ -0,0 +1,212 @@
+use crate::board::Board;
+use crate::r#move::Move;
+use crate::search::MAX_PLY;
+use std::time::Duration;
+
+/// Moves left until the next time control when the GUI does not say.
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+/// Parameters of the UCI `go` command, which end a `searchmoves` list.
+const GO_PARAMETERS: [&str; 12] = [
+    "searchmoves",
+    "ponder",
+    "wtime",
+    "btime",
+    "winc",
+    "binc",
+    "movestogo",
+    "depth",
+    "nodes",
+    "mate",
+    "movetime",
+    "infinite",
+];
+
+/// Time kept in reserve for communication with the GUI.
+const MOVE_OVERHEAD: Duration = Duration::from_millis(30);
+
+/// When a search stops. Every limit that is set applies, the search ending
+/// at the first one reached; with none set it runs until stopped.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<Duration>,
+    /// Remaining time and increment of each side, for the engine to decide
+    /// how long to think.
+    pub wtime: Option<Duration>,
+    pub btime: Option<Duration>,
+    pub winc: Option<Duration>,
+    pub binc: Option<Duration>,
+    pub movestogo: Option<u32>,
+    /// Stop once a mate in this many moves is found.
+    pub mate: Option<u32>,
+    /// Only consider these root moves, all of them when empty.
+    pub searchmoves: Vec<Move>,
+    /// Ignore every other limit and search until stopped.
+    pub infinite: bool,
+    /// Search on the opponent's time, in the position after the reply the
+    /// engine expects. Time limits only start counting from the ponderhit,
+    /// see `Engine::pondering_flag`.
+    pub ponder: bool,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: u32) -> SearchLimits {
+        SearchLimits {
+            depth: Some(depth),
+            ..SearchLimits::default()
+        }
+    }
+
+    pub fn movetime(movetime: Duration) -> SearchLimits {
+        SearchLimits {
+            movetime: Some(movetime),
+            ..SearchLimits::default()
+        }
+    }
+
+    pub fn nodes(nodes: u64) -> SearchLimits {
+        SearchLimits {
+            nodes: Some(nodes),
+            ..SearchLimits::default()
+        }
+    }
+
+    /// Deepest iteration of iterative deepening.
+    pub fn max_depth(&self) -> u32 {
+        match self.depth {
+            Some(depth) if !self.infinite => depth.clamp(1, MAX_PLY),
+            _ => MAX_PLY,
+        }
+    }
+
+    /// Node count to stop at, if any.
+    pub fn max_nodes(&self) -> Option<u64> {
+        self.nodes.filter(|_| !self.infinite)
+    }
+
+    /// How long to think with `white` to move: `movetime`, or a share of
+    /// the remaining clock time plus most of the increment, whichever is
+    /// shorter.
+    pub fn time_budget(&self, white: bool) -> Option<Duration> {
+        if self.infinite {
+            return None;
+        }
+
+        let (time, inc) = if white {
+            (self.wtime, self.winc)
+        } else {
+            (self.btime, self.binc)
+        };
+
+        let from_clock = time.map(|time| {
+            let moves = self.movestogo.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+            let inc = inc.unwrap_or_default();
+
+            (time / moves + inc * 3 / 4).min(time.saturating_sub(MOVE_OVERHEAD))
+        });
+
+        match (self.movetime, from_clock) {
+            (Some(movetime), Some(from_clock)) => Some(movetime.min(from_clock)),
+            (movetime, from_clock) => movetime.or(from_clock),
+        }
+    }
+
+    /// Parses the arguments of a UCI `go` command, with `board` the position
+    /// to search for `searchmoves`. Unknown parameters and illegal moves
+    /// after `searchmoves` are ignored, only malformed values are errors.
+    pub fn from_go(input: &str, board: &Board) -> Result<SearchLimits, String> {
+        let mut limits = SearchLimits::default();
+        let mut tokens = input.split_whitespace().skip(1).peekable();
+
+        fn value<T: std::str::FromStr>(name: &str, token: Option<&str>) -> Result<T, String> {
+            let token = token.ok_or_else(|| format!("missing value for {}", name))?;
+            token
+                .parse()
+                .map_err(|_| format!("invalid value for {}: {}", name, token))
+        }
+
+        let millis = |name, token| value(name, token).map(Duration::from_millis);
+
+        while let Some(token) = tokens.next() {
+            match token {
+                "depth" => limits.depth = Some(value(token, tokens.next())?),
+                "nodes" => limits.nodes = Some(value(token, tokens.next())?),
+                "mate" => limits.mate = Some(value(token, tokens.next())?),
+                "movestogo" => limits.movestogo = Some(value(token, tokens.next())?),
+                "movetime" => limits.movetime = Some(millis(token, tokens.next())?),
+                "wtime" => limits.wtime = Some(millis(token, tokens.next())?),
+                "btime" => limits.btime = Some(millis(token, tokens.next())?),
+                "winc" => limits.winc = Some(millis(token, tokens.next())?),
+                "binc" => limits.binc = Some(millis(token, tokens.next())?),
+                "infinite" => limits.infinite = true,
+                "ponder" => limits.ponder = true,
+                "searchmoves" => {
+                    while let Some(m) = tokens.next_if(|m| !GO_PARAMETERS.contains(m)) {
+                        if let Ok(m) = board.parse_uci(m) {
+                            limits.searchmoves.push(m);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(limits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_go() {
+        let board = Board::new_game();
+
+        let limits =
+            SearchLimits::from_go("go wtime 60000 btime 30000 winc 1000 movestogo 20", &board)
+                .unwrap();
+        assert_eq!(limits.wtime, Some(Duration::from_secs(60)));
+        assert_eq!(limits.movestogo, Some(20));
+        assert_eq!(limits.max_depth(), MAX_PLY);
+
+        let limits = SearchLimits::from_go("go searchmoves e2e4 d2d4 depth 5", &board).unwrap();
+        assert_eq!(limits.searchmoves.len(), 2);
+        assert_eq!(limits.max_depth(), 5);
+
+        let limits = SearchLimits::from_go("go ponder wtime 1000 btime 1000", &board).unwrap();
+        assert!(limits.ponder);
+
+        // Illegal moves and unknown parameters are skipped
+        let limits =
+            SearchLimits::from_go("go searchmoves e2e5 g1f3 sideways depth 2", &board).unwrap();
+        assert_eq!(limits.searchmoves, vec![Move::from_str("g1f3")]);
+        assert_eq!(limits.max_depth(), 2);
+
+        assert!(SearchLimits::from_go("go depth", &board).is_err());
+        assert!(SearchLimits::from_go("go nodes many", &board).is_err());
+    }
+
+    #[test]
+    fn test_time_budget() {
+        let limits = SearchLimits {
+            wtime: Some(Duration::from_secs(30)),
+            btime: Some(Duration::from_secs(9)),
+            binc: Some(Duration::from_secs(1)),
+            ..SearchLimits::default()
+        };
+
+        assert_eq!(limits.time_budget(true), Some(Duration::from_secs(1)));
+        assert_eq!(limits.time_budget(false), Some(Duration::from_millis(1050)));
+
+        let limits = SearchLimits {
+            movetime: Some(Duration::from_millis(200)),
+            ..limits
+        };
+        assert_eq!(limits.time_budget(true), Some(Duration::from_millis(200)));
+
+        assert_eq!(SearchLimits::depth(3).time_budget(true), None);
+    }
+}
@@ -1,18 +1,26 @@
-use crate::board::Board;
-use crate::engine::Engine;
+use chess_engine::Engine;
+use std::process::ExitCode;
 
-mod bitboard;
-mod board;
-mod cache;
-mod coord;
-mod engine;
-mod evaluate;
-mod r#move;
-mod moves;
-mod piece;
+mod cli;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let command = match cli::parse_args(&args) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            return ExitCode::from(2);
+        }
+    };
 
-fn main() {
     let mut engine = Engine::new();
 
-    let m = engine.run();
+    match cli::run(&mut engine, command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
 }
@@ -0,0 +1,205 @@
+use crate::board::Board;
+use crate::cache::pawn::PAWN_ATTACKS;
+use crate::cache::piece_attacks;
+use crate::coord::Coord;
+use crate::params::EvalParams;
+use crate::pawns::{adjacent_files_mask, file_mask, ranks_ahead};
+
+/// Squares attacked by `color`'s pawns.
+fn pawn_attacks(board: &Board, color: bool) -> u64 {
+    let mut pawns = board.get_piece_index_bitboard(0, color).get_board();
+    let mut attacks = 0;
+
+    while pawns != 0 {
+        let index = pawns.trailing_zeros() as usize;
+        pawns &= pawns - 1;
+        attacks |= PAWN_ATTACKS[color as usize][index].get_board();
+    }
+
+    attacks
+}
+
+/// `coord` seen from `color`'s side, so that white and black patterns can
+/// be written once from white's point of view.
+fn relative(coord: Coord, color: bool) -> Coord {
+    if color {
+        coord
+    } else {
+        Coord::new(coord.x, 7 - coord.y)
+    }
+}
+
+fn has_piece(board: &Board, index: u8, color: bool, coord: Coord) -> bool {
+    board
+        .get_piece_index_bitboard(index, color)
+        .get(relative(coord, color))
+}
+
+/// Bishops shut in on a7/h7 (a2/h2 for black) by an enemy pawn, and rooks
+/// boxed in by their own uncastled king.
+fn trapped_pieces(board: &Board, color: bool, params: &EvalParams) -> (i16, i16) {
+    let mut mg = 0;
+    let mut eg = 0;
+
+    for (bishop, pawn) in [((0, 6), (1, 5)), ((7, 6), (6, 5))] {
+        if has_piece(board, 2, color, Coord::new(bishop.0, bishop.1))
+            && board
+                .get_piece_index_bitboard(0, !color)
+                .get(relative(Coord::new(pawn.0, pawn.1), color))
+        {
+            mg += params.trapped_bishop[0];
+            eg += params.trapped_bishop[1];
+        }
+    }
+
+    let king_side = [(5, 0), (6, 0)]
+        .iter()
+        .any(|&(x, y)| has_piece(board, 5, color, Coord::new(x, y)))
+        && [(6, 0), (7, 0), (7, 1)]
+            .iter()
+            .any(|&(x, y)| has_piece(board, 3, color, Coord::new(x, y)));
+    let queen_side = [(1, 0), (2, 0)]
+        .iter()
+        .any(|&(x, y)| has_piece(board, 5, color, Coord::new(x, y)))
+        && [(0, 0), (1, 0), (0, 1)]
+            .iter()
+            .any(|&(x, y)| has_piece(board, 3, color, Coord::new(x, y)));
+
+    if king_side || queen_side {
+        mg += params.trapped_rook[0];
+        eg += params.trapped_rook[1];
+    }
+
+    (mg, eg)
+}
+
+/// Mobility and piece activity score of `color`'s pieces alone, as
+/// `(middlegame, endgame)`.
+pub fn evaluate_mobility_side(board: &Board, color: bool, params: &EvalParams) -> (i16, i16) {
+    let occupied = board.occupied_bitboard();
+    let own = board.get_piece_bitboard(color).get_board();
+    let own_pawns = board.get_piece_index_bitboard(0, color).get_board();
+    let enemy_pawns = board.get_piece_index_bitboard(0, !color).get_board();
+    let enemy_pawn_attacks = pawn_attacks(board, !color);
+    let available = !own & !enemy_pawn_attacks;
+
+    let mut mg = 0;
+    let mut eg = 0;
+
+    for index in 1..5u8 {
+        let mut pieces = board.get_piece_index_bitboard(index, color).get_board();
+
+        while pieces != 0 {
+            let coord = Coord::from_index(pieces.trailing_zeros() as u8);
+            pieces &= pieces - 1;
+
+            let attacks = piece_attacks(index, coord, occupied).get_board();
+            let count = (attacks & available).count_ones() as i16
+                - params.mobility_baseline[index as usize];
+            mg += params.mobility[0][index as usize] * count;
+            eg += params.mobility[1][index as usize] * count;
+
+            let rank = relative(coord, color).y;
+            let file = file_mask(coord.x);
+
+            if index == 1 {
+                // Outpost: defended by a pawn, and no enemy pawn can ever
+                // chase the knight away
+                let defended = PAWN_ATTACKS[!color as usize][coord.to_index() as usize].get_board()
+                    & own_pawns
+                    != 0;
+                let chasers =
+                    adjacent_files_mask(coord.x) & ranks_ahead(coord.y, color) & enemy_pawns;
+
+                if (3..=5).contains(&rank) && defended && chasers == 0 {
+                    mg += params.knight_outpost[0];
+                    eg += params.knight_outpost[1];
+                }
+            }
+
+            if index == 3 {
+                if file & (own_pawns | enemy_pawns) == 0 {
+                    mg += params.rook_on_open_file[0];
+                    eg += params.rook_on_open_file[1];
+                } else if file & own_pawns == 0 {
+                    mg += params.rook_on_semi_open_file[0];
+                    eg += params.rook_on_semi_open_file[1];
+                }
+
+                // The seventh only matters with pawns to eat or the king
+                // cut off on the back rank
+                let seventh = 0xFFu64 << (coord.y * 8);
+                let back_rank_king = board
+                    .get_piece_index_coord(5, !color)
+                    .is_some_and(|king| relative(king, color).y == 7);
+
+                if rank == 6 && (seventh & enemy_pawns != 0 || back_rank_king) {
+                    mg += params.rook_on_seventh[0];
+                    eg += params.rook_on_seventh[1];
+                }
+            }
+        }
+    }
+
+    if board
+        .get_piece_index_bitboard(2, color)
+        .get_board()
+        .count_ones()
+        >= 2
+    {
+        mg += params.bishop_pair[0];
+        eg += params.bishop_pair[1];
+    }
+
+    let (trapped_mg, trapped_eg) = trapped_pieces(board, color, params);
+
+    (mg + trapped_mg, eg + trapped_eg)
+}
+
+/// Mobility and piece activity score, white minus black, as
+/// `(middlegame, endgame)`.
+pub fn evaluate_mobility(board: &Board, params: &EvalParams) -> (i16, i16) {
+    let (white_mg, white_eg) = evaluate_mobility_side(board, true, params);
+    let (black_mg, black_eg) = evaluate_mobility_side(board, false, params);
+
+    (white_mg - black_mg, white_eg - black_eg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::params::DEFAULT_PARAMS;
+
+    fn mobility(fen: &str) -> (i16, i16) {
+        evaluate_mobility(&Board::from_fen(fen).unwrap(), &DEFAULT_PARAMS)
+    }
+
+    #[test]
+    fn test_symmetric_start() {
+        assert_eq!(mobility(Board::STARTING_FEN), (0, 0));
+    }
+
+    #[test]
+    fn test_bishop_pair() {
+        let pair = mobility("4k3/8/8/8/8/8/8/2B1KB2 w");
+        let single = mobility("4k3/8/8/8/8/8/8/2N1KB2 w");
+
+        assert!(pair.1 > single.1);
+    }
+
+    #[test]
+    fn test_rook_on_open_file() {
+        let open = mobility("4k3/p7/8/8/8/8/P7/3RK3 w");
+        let closed = mobility("4k3/3p4/8/8/8/8/3P4/3RK3 w");
+
+        assert!(open.0 > closed.0);
+    }
+
+    #[test]
+    fn test_trapped_bishop() {
+        let trapped = mobility("4k3/B7/1p6/8/8/8/8/4K3 w");
+        let free = mobility("4k3/B7/8/8/8/8/8/4K3 w");
+
+        assert!(trapped.0 < free.0 - 50);
+    }
+}
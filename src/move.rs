@@ -1,9 +1,7 @@
-use crate::bitboard::BitBoard;
 use crate::coord::Coord;
-use crate::piece::Piece;
 use std::fmt::{Debug, Display};
 
-#[derive(PartialEq, Clone, Copy)]
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
 pub struct Move {
     pub from: Coord,
     pub to: Coord,
@@ -34,7 +32,12 @@ impl Move {
         self.capture
     }
 
-    pub fn from_str(s: &str) -> Move {
+    /// Parses coordinate notation such as `e2e4`. Panics if either square
+    /// is malformed, so it is only for tests; see `Board::parse_uci` for
+    /// validated input.
+    #[cfg(test)]
+    #[allow(clippy::should_implement_trait)]
+    pub(crate) fn from_str(s: &str) -> Move {
         let from = Coord::from_str(&s[0..2]).unwrap();
         let to = Coord::from_str(&s[2..4]).unwrap();
 
@@ -55,7 +58,7 @@ impl Move {
         Move::new(from, to, capture, promotion)
     }
 
-    pub fn to_str(&self) -> String {
+    pub fn to_str(self) -> String {
         let mut s = format!("{}{}", self.from.to_str(), self.to.to_str());
 
         if self.promotion {
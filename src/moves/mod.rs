@@ -1,4 +1,4 @@
-use crate::bitboard::BitBoard;
+use crate::bitboard::{BitBoard, EMPTY};
 use crate::board::Board;
 use crate::cache::bishop::get_bishop_moves;
 use crate::cache::bitboard_to_moves;
@@ -10,22 +10,13 @@ use crate::coord::Coord;
 use crate::r#move::Move;
 
 pub fn filter_check_moves(board: &Board, moves: Vec<Move>) -> Vec<Move> {
-    let mut filtered_moves = Vec::new();
-    for m in moves {
-        let mut board_copy = board.clone();
-        board_copy.make_move(m);
-        if !board_copy.is_check(board.turn) {
-            filtered_moves.push(m);
-        }
-    }
-
-    filtered_moves
+    moves.into_iter().filter(|m| board.is_legal(*m)).collect()
 }
 
 pub fn generate_knights_moves(board: &Board, coord: Coord, color: bool) -> Vec<Move> {
     let knight_moves_bitboard = KNIGHT_MOVES[coord.to_index() as usize];
     let legal_moves_bitboard = knight_moves_bitboard & !board.occupied_bitboard();
-    let legal_attacks_bitboard = knight_moves_bitboard & !board.get_piece_bitboard(color);
+    let legal_attacks_bitboard = knight_moves_bitboard & board.get_enemy_bitboard(color);
 
     let mut moves = bitboard_to_moves(coord, knight_moves_bitboard & legal_moves_bitboard, false);
     moves.append(&mut bitboard_to_moves(
@@ -38,12 +29,23 @@ pub fn generate_knights_moves(board: &Board, coord: Coord, color: bool) -> Vec<M
 }
 
 pub fn generate_pawn_moves(board: &Board, coord: Coord, color: bool) -> Vec<Move> {
-    let pawn_moves_bitboard = PAWN_MOVES[color as usize][coord.to_index() as usize];
+    let mut pawn_moves_bitboard = PAWN_MOVES[color as usize][coord.to_index() as usize];
+
+    // A blocked pawn cannot jump over the blocker on its double push
+    let single_push = if color {
+        coord.to_index() + 8
+    } else {
+        coord.to_index().wrapping_sub(8)
+    };
+    if single_push < 64 && board.occupied_bitboard().get_index(single_push) {
+        pawn_moves_bitboard = EMPTY;
+    }
+
     let legal_moves_bitboard = pawn_moves_bitboard & !board.occupied_bitboard();
 
     let pawn_attacks_bitboard =
         PAWN_ATTACKS[color as usize][coord.to_index() as usize] & board.get_enemy_bitboard(color);
-    let legal_attacks_bitboard = pawn_attacks_bitboard & !board.get_piece_bitboard(color);
+    let legal_attacks_bitboard = pawn_attacks_bitboard & board.get_enemy_bitboard(color);
 
     let mut moves = bitboard_to_moves(coord, pawn_moves_bitboard & legal_moves_bitboard, false);
 
@@ -61,7 +63,7 @@ pub fn generate_bishop_moves(board: &Board, coord: Coord, color: bool) -> Vec<Mo
         get_bishop_moves(BitBoard::from_coord(coord), board.occupied_bitboard());
 
     let legal_moves_bitboard = bishop_moves_bitboard & !board.occupied_bitboard();
-    let legal_attacks_bitboard = bishop_moves_bitboard & !board.get_piece_bitboard(color);
+    let legal_attacks_bitboard = bishop_moves_bitboard & board.get_enemy_bitboard(color);
 
     // Apply blockers
     let mut moves = bitboard_to_moves(coord, bishop_moves_bitboard & legal_moves_bitboard, false);
@@ -80,7 +82,7 @@ pub fn generate_rook_moves(board: &Board, coord: Coord, color: bool) -> Vec<Move
         get_rook_moves(BitBoard::from_coord(coord), board.occupied_bitboard());
 
     let legal_moves_bitboard = rook_moves_bitboard & !board.occupied_bitboard();
-    let legal_attacks_bitboard = rook_moves_bitboard & !board.get_piece_bitboard(color);
+    let legal_attacks_bitboard = rook_moves_bitboard & board.get_enemy_bitboard(color);
 
     // Apply blockers
     let mut moves = bitboard_to_moves(coord, rook_moves_bitboard & legal_moves_bitboard, false);
@@ -103,7 +105,7 @@ pub fn generate_queen_moves(board: &Board, coord: Coord, color: bool) -> Vec<Mov
 pub fn generate_king_moves(board: &Board, coord: Coord, color: bool) -> Vec<Move> {
     let king_moves_bitboard = KING_MOVES[coord.to_index() as usize];
     let legal_moves_bitboard = king_moves_bitboard & !board.occupied_bitboard();
-    let legal_attacks_bitboard = king_moves_bitboard & !board.get_piece_bitboard(color);
+    let legal_attacks_bitboard = king_moves_bitboard & board.get_enemy_bitboard(color);
 
     let mut moves = bitboard_to_moves(coord, king_moves_bitboard & legal_moves_bitboard, false);
     moves.append(&mut bitboard_to_moves(
@@ -0,0 +1,451 @@
+//! Efficiently updatable neural network evaluation.
+//!
+//! The network is `(768 -> N) x 2 -> 1`: each side has its own view of the
+//! board, 768 features for every (own or enemy, piece, square) combination,
+//! with squares flipped vertically for black. Both views go through the same
+//! feature transformer into an accumulator of `N` values, which is kept up to
+//! date as pieces are set and unset, so only the small output layer runs per
+//! evaluation. The accumulator of the side to move comes first in the output
+//! layer, the hidden activation is clipped ReLU.
+//!
+//! Networks are stored in a little-endian binary file:
+//!
+//! | Field             | Type  | Count     |
+//! |-------------------|-------|-----------|
+//! | magic `NNUE`      | bytes | 4         |
+//! | hidden size `N`   | u32   | 1         |
+//! | feature weights   | i16   | `768 * N` |
+//! | feature biases    | i16   | `N`       |
+//! | output weights    | i16   | `2 * N`   |
+//! | output bias       | i32   | 1         |
+//!
+//! Feature weights are stored feature by feature, with feature index
+//! `own_or_enemy * 384 + piece * 64 + square`. The feature transformer is
+//! quantized by `QA`, the output weights by `QB` and the output bias by
+//! `QA * QB`. The dequantized output is multiplied by `SCALE` to get
+//! centipawns.
+
+use crate::board::Board;
+use crate::coord::Coord;
+use crate::piece::Piece;
+use std::sync::Arc;
+
+pub const INPUTS: usize = 768;
+pub const QA: i32 = 255;
+pub const QB: i32 = 64;
+pub const SCALE: i32 = 400;
+
+const MAGIC: &[u8; 4] = b"NNUE";
+
+pub struct Network {
+    hidden: usize,
+    feature_weights: Vec<i16>,
+    feature_biases: Vec<i16>,
+    output_weights: Vec<i16>,
+    output_bias: i32,
+}
+
+/// Reads little-endian values off the front of a byte slice.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        if self.bytes.len() < N {
+            return Err("truncated network file".to_string());
+        }
+
+        let (value, rest) = self.bytes.split_at(N);
+        self.bytes = rest;
+
+        Ok(value.try_into().unwrap())
+    }
+
+    fn i16s(&mut self, count: usize) -> Result<Vec<i16>, String> {
+        (0..count)
+            .map(|_| self.take().map(i16::from_le_bytes))
+            .collect()
+    }
+}
+
+impl Network {
+    pub fn new(
+        feature_weights: Vec<i16>,
+        feature_biases: Vec<i16>,
+        output_weights: Vec<i16>,
+        output_bias: i32,
+    ) -> Result<Network, String> {
+        let hidden = feature_biases.len();
+
+        if hidden == 0
+            || feature_weights.len() != INPUTS * hidden
+            || output_weights.len() != 2 * hidden
+        {
+            return Err("inconsistent network sizes".to_string());
+        }
+
+        Ok(Network {
+            hidden,
+            feature_weights,
+            feature_biases,
+            output_weights,
+            output_bias,
+        })
+    }
+
+    pub fn hidden_size(&self) -> usize {
+        self.hidden
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Network, String> {
+        let mut reader = Reader { bytes };
+
+        if &reader.take::<4>()? != MAGIC {
+            return Err("not a network file".to_string());
+        }
+
+        let hidden = u32::from_le_bytes(reader.take()?) as usize;
+        let feature_weights = reader.i16s(INPUTS * hidden)?;
+        let feature_biases = reader.i16s(hidden)?;
+        let output_weights = reader.i16s(2 * hidden)?;
+        let output_bias = i32::from_le_bytes(reader.take()?);
+
+        if !reader.bytes.is_empty() {
+            return Err("trailing data after network".to_string());
+        }
+
+        Network::new(feature_weights, feature_biases, output_weights, output_bias)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend((self.hidden as u32).to_le_bytes());
+
+        for values in [
+            &self.feature_weights,
+            &self.feature_biases,
+            &self.output_weights,
+        ] {
+            for value in values {
+                bytes.extend(value.to_le_bytes());
+            }
+        }
+
+        bytes.extend(self.output_bias.to_le_bytes());
+        bytes
+    }
+
+    pub fn load(path: &str) -> Result<Network, String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+
+        Network::from_bytes(&bytes).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        std::fs::write(path, self.to_bytes())
+    }
+
+    /// Index of `piece` on `coord` among the features of `perspective`.
+    fn feature(perspective: bool, piece: Piece, coord: Coord) -> usize {
+        let square = if perspective {
+            coord.to_index() as usize
+        } else {
+            coord.to_index() as usize ^ 56
+        };
+        let enemy = (piece.is_white() != perspective) as usize;
+
+        enemy * 384 + piece.to_index() as usize * 64 + square
+    }
+
+    fn weights(&self, feature: usize) -> &[i16] {
+        &self.feature_weights[feature * self.hidden..(feature + 1) * self.hidden]
+    }
+
+    /// Score of the position behind `accumulator`, positive when the side
+    /// to move, `turn`, is better.
+    pub(crate) fn evaluate(&self, accumulator: &Accumulator, turn: bool) -> i16 {
+        let (us, them) = (
+            &accumulator.values[turn as usize],
+            &accumulator.values[!turn as usize],
+        );
+        let output = self
+            .output_bias
+            .wrapping_add(crelu_dot(us, &self.output_weights[..self.hidden]))
+            .wrapping_add(crelu_dot(them, &self.output_weights[self.hidden..]));
+
+        (output as i64 * SCALE as i64 / (QA * QB) as i64)
+            .clamp(-(i16::MAX as i64) / 2, i16::MAX as i64 / 2) as i16
+    }
+
+    /// Evaluates `board` from scratch, without incremental updates.
+    pub fn evaluate_board(&self, board: &Board) -> i16 {
+        self.evaluate(&Accumulator::from_board(self, board), board.turn)
+    }
+}
+
+/// Sum of `clamp(value, 0, QA) * weight`, the output layer of the network.
+/// Wraps around on overflow, as the SIMD additions do.
+fn crelu_dot(values: &[i16], weights: &[i16]) -> i32 {
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("avx2") {
+        // Safety: AVX2 support was just checked
+        return unsafe { crelu_dot_avx2(values, weights) };
+    }
+
+    crelu_dot_scalar(values, weights)
+}
+
+fn crelu_dot_scalar(values: &[i16], weights: &[i16]) -> i32 {
+    values
+        .iter()
+        .zip(weights)
+        .map(|(&value, &weight)| value.clamp(0, QA as i16) as i32 * weight as i32)
+        .fold(0, i32::wrapping_add)
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn crelu_dot_avx2(values: &[i16], weights: &[i16]) -> i32 {
+    use std::arch::x86_64::*;
+
+    let length = values.len().min(weights.len());
+    let chunks = length / 16;
+    let zero = _mm256_setzero_si256();
+    let max = _mm256_set1_epi16(QA as i16);
+    let mut sum = _mm256_setzero_si256();
+
+    for i in 0..chunks {
+        // Safety: both slices hold at least `chunks * 16` values
+        let (value, weight) = unsafe {
+            (
+                _mm256_loadu_si256(values.as_ptr().add(i * 16) as *const __m256i),
+                _mm256_loadu_si256(weights.as_ptr().add(i * 16) as *const __m256i),
+            )
+        };
+        let clipped = _mm256_min_epi16(_mm256_max_epi16(value, zero), max);
+        sum = _mm256_add_epi32(sum, _mm256_madd_epi16(clipped, weight));
+    }
+
+    let mut lanes = [0i32; 8];
+    // Safety: `lanes` is exactly one 256-bit vector
+    unsafe { _mm256_storeu_si256(lanes.as_mut_ptr() as *mut __m256i, sum) };
+
+    lanes
+        .into_iter()
+        .fold(0, i32::wrapping_add)
+        .wrapping_add(crelu_dot_scalar(
+            &values[chunks * 16..length],
+            &weights[chunks * 16..length],
+        ))
+}
+
+/// The hidden layer before activation, one half per perspective, indexed
+/// by color.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Accumulator {
+    values: [Vec<i16>; 2],
+}
+
+impl Accumulator {
+    pub fn new(network: &Network) -> Accumulator {
+        Accumulator {
+            values: [
+                network.feature_biases.clone(),
+                network.feature_biases.clone(),
+            ],
+        }
+    }
+
+    pub fn from_board(network: &Network, board: &Board) -> Accumulator {
+        let mut accumulator = Accumulator::new(network);
+
+        for i in 0..64 {
+            let coord = Coord::from_index(i);
+
+            if let Some(piece) = board.get_piece(coord) {
+                accumulator.update(network, piece, coord, true);
+            }
+        }
+
+        accumulator
+    }
+
+    /// Adds the features of `piece` on `coord`, or removes them. Sums wrap
+    /// around like the release build and the SIMD code, so that no weights
+    /// in a well-formed file can make a debug build panic.
+    pub fn update(&mut self, network: &Network, piece: Piece, coord: Coord, added: bool) {
+        for perspective in [true, false] {
+            let weights = network.weights(Network::feature(perspective, piece, coord));
+            let values = &mut self.values[perspective as usize];
+
+            if added {
+                for (value, weight) in values.iter_mut().zip(weights) {
+                    *value = value.wrapping_add(*weight);
+                }
+            } else {
+                for (value, weight) in values.iter_mut().zip(weights) {
+                    *value = value.wrapping_sub(*weight);
+                }
+            }
+        }
+    }
+}
+
+/// A stack of accumulators following the moves made on a board: one entry
+/// per ply, so undoing a move only has to pop.
+#[derive(Clone)]
+pub struct NnueState {
+    network: Arc<Network>,
+    stack: Vec<Accumulator>,
+    top: usize,
+}
+
+impl NnueState {
+    pub fn new(network: Arc<Network>, board: &Board) -> NnueState {
+        let accumulator = Accumulator::from_board(&network, board);
+
+        NnueState {
+            network,
+            stack: vec![accumulator],
+            top: 0,
+        }
+    }
+
+    pub fn network(&self) -> &Arc<Network> {
+        &self.network
+    }
+
+    pub fn current(&self) -> &Accumulator {
+        &self.stack[self.top]
+    }
+
+    /// Copies the current accumulator on top of the stack, reusing the
+    /// entries of earlier plies to avoid allocating.
+    pub fn push(&mut self) {
+        if self.top + 1 == self.stack.len() {
+            self.stack.push(self.stack[self.top].clone());
+        } else {
+            let (below, above) = self.stack.split_at_mut(self.top + 1);
+            for (to, from) in above[0].values.iter_mut().zip(&below[self.top].values) {
+                to.copy_from_slice(from);
+            }
+        }
+
+        self.top += 1;
+    }
+
+    pub fn pop(&mut self) {
+        self.top = self.top.saturating_sub(1);
+    }
+
+    pub fn update(&mut self, piece: Piece, coord: Coord, added: bool) {
+        self.stack[self.top].update(&self.network, piece, coord, added);
+    }
+
+    pub fn evaluate(&self, turn: bool) -> i16 {
+        self.network.evaluate(self.current(), turn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::r#move::Move;
+
+    /// A small network with pseudo-random weights, enough to check that
+    /// incremental updates and the file format are consistent.
+    fn random_network(hidden: usize) -> Network {
+        let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+        let mut next = |range: i16| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % (2 * range as u64 + 1)) as i16 - range
+        };
+
+        let feature_weights = (0..INPUTS * hidden).map(|_| next(40)).collect();
+        let feature_biases = (0..hidden).map(|_| next(40)).collect();
+        let output_weights = (0..2 * hidden).map(|_| next(60)).collect();
+
+        Network::new(feature_weights, feature_biases, output_weights, 1000).unwrap()
+    }
+
+    #[test]
+    fn test_file_round_trip() {
+        let network = random_network(8);
+        let loaded = Network::from_bytes(&network.to_bytes()).unwrap();
+
+        assert_eq!(loaded.to_bytes(), network.to_bytes());
+        assert!(Network::from_bytes(&network.to_bytes()[..100]).is_err());
+        assert!(Network::from_bytes(b"NOPE").is_err());
+    }
+
+    #[test]
+    fn test_incremental_matches_refresh() {
+        let network = Arc::new(random_network(32));
+        let mut board = Board::new_game();
+        board.set_network(Some(network.clone()));
+
+        let moves = ["e2e4", "d7d5", "e4d5", "d8d5", "b1c3"].map(Move::from_str);
+        for m in moves {
+            board.make_move(m);
+            assert_eq!(board.evaluate_nnue(), Some(network.evaluate_board(&board)));
+        }
+
+        for m in moves.iter().rev() {
+            board.undo_move(*m);
+        }
+
+        assert_eq!(board.evaluate_nnue(), Some(network.evaluate_board(&board)));
+        assert_eq!(board.evaluate_nnue(), {
+            let fresh = Board::new_game();
+            Some(network.evaluate_board(&fresh))
+        });
+    }
+
+    #[test]
+    fn test_extreme_weights_wrap() {
+        let hidden = 4;
+        let network = Arc::new(
+            Network::new(
+                vec![i16::MAX; INPUTS * hidden],
+                vec![i16::MIN; hidden],
+                vec![i16::MAX; 2 * hidden],
+                i32::MAX,
+            )
+            .unwrap(),
+        );
+
+        let mut board = Board::new_game();
+        board.set_network(Some(network.clone()));
+        board.make_move(Move::from_str("e2e4"));
+        assert_eq!(board.evaluate_nnue(), Some(network.evaluate_board(&board)));
+    }
+
+    #[test]
+    fn test_search_with_network() {
+        let mut engine = crate::engine::Engine::new();
+        engine.set_evaluator(Box::new(crate::evaluator::NnueEvaluator::new(Arc::new(
+            random_network(16),
+        ))));
+
+        let m = engine.get_best_move(2);
+        assert!(engine.get_all_moves().contains(&m));
+    }
+
+    #[test]
+    fn test_simd_matches_scalar() {
+        let network = random_network(48);
+        let accumulator = Accumulator::from_board(&network, &Board::new_game());
+        let values = &accumulator.values[1];
+
+        // A whole number of vectors, and one with a scalar tail
+        for length in [48, 37] {
+            assert_eq!(
+                crelu_dot(&values[..length], &network.output_weights[..length]),
+                crelu_dot_scalar(&values[..length], &network.output_weights[..length])
+            );
+        }
+    }
+}
@@ -0,0 +1,345 @@
+use crate::board::Board;
+use crate::coord::Coord;
+use crate::evaluate::*;
+use crate::piece::Piece;
+use crate::trace::{EvalTerm, EvalTrace};
+use lazy_static::lazy_static;
+use std::fmt::Display;
+
+const PIECE_NAMES: [&str; 6] = ["pawn", "knight", "bishop", "rook", "queen", "king"];
+
+lazy_static! {
+    pub static ref DEFAULT_PARAMS: EvalParams = EvalParams::default();
+}
+
+/// Every weight of the classic evaluation, so it can be tuned and loaded
+/// from a file instead of being compiled in. Defaults are the constants of
+/// `evaluate`.
+///
+/// Pairs are stored as `[middlegame, endgame]` like the constants, and the
+/// piece-square tables from white's side, `a1` first; black reads them
+/// mirrored.
+#[derive(Clone, PartialEq, Debug)]
+pub struct EvalParams {
+    pub material: [[i16; 6]; 2],
+    pub pst: [[[i16; 64]; 6]; 2],
+    pub passed_pawn: [[i16; 8]; 2],
+    pub candidate_pawn: [[i16; 8]; 2],
+    pub connected_pawn: [[i16; 8]; 2],
+    pub isolated_pawn: [i16; 2],
+    pub doubled_pawn: [i16; 2],
+    pub backward_pawn: [i16; 2],
+    pub pawn_island: [i16; 2],
+    pub pawn_shield: [[i16; 4]; 2],
+    pub pawn_storm: [[i16; 4]; 2],
+    pub semi_open_file_near_king: [i16; 2],
+    pub open_file_near_king: [i16; 2],
+    pub king_attack_weights: [i16; 6],
+    pub king_danger: [i16; 64],
+    pub mobility: [[i16; 6]; 2],
+    pub mobility_baseline: [i16; 6],
+    pub bishop_pair: [i16; 2],
+    pub knight_outpost: [i16; 2],
+    pub rook_on_open_file: [i16; 2],
+    pub rook_on_semi_open_file: [i16; 2],
+    pub rook_on_seventh: [i16; 2],
+    pub trapped_bishop: [i16; 2],
+    pub trapped_rook: [i16; 2],
+    pub term_weights: [i16; 5],
+}
+
+impl Default for EvalParams {
+    fn default() -> Self {
+        let mg = [
+            &PAWN_SCORE,
+            &KNIGHT_SCORE,
+            &BISHOP_SCORE,
+            &ROOK_SCORE,
+            &QUEEN_SCORE,
+            &KING_SCORE,
+        ];
+        let eg = [
+            &PAWN_SCORE_EG,
+            &KNIGHT_SCORE_EG,
+            &BISHOP_SCORE_EG,
+            &ROOK_SCORE_EG,
+            &QUEEN_SCORE_EG,
+            &KING_SCORE_EG,
+        ];
+
+        Self {
+            material: [MATERIAL_MG, MATERIAL_EG],
+            pst: [mg.map(|table| table[1]), eg.map(|table| table[1])],
+            passed_pawn: PASSED_PAWN,
+            candidate_pawn: CANDIDATE_PAWN,
+            connected_pawn: CONNECTED_PAWN,
+            isolated_pawn: ISOLATED_PAWN,
+            doubled_pawn: DOUBLED_PAWN,
+            backward_pawn: BACKWARD_PAWN,
+            pawn_island: PAWN_ISLAND,
+            pawn_shield: PAWN_SHIELD,
+            pawn_storm: PAWN_STORM,
+            semi_open_file_near_king: SEMI_OPEN_FILE_NEAR_KING,
+            open_file_near_king: OPEN_FILE_NEAR_KING,
+            king_attack_weights: KING_ATTACK_WEIGHTS,
+            king_danger: KING_DANGER,
+            mobility: MOBILITY,
+            mobility_baseline: MOBILITY_BASELINE,
+            bishop_pair: BISHOP_PAIR,
+            knight_outpost: KNIGHT_OUTPOST,
+            rook_on_open_file: ROOK_ON_OPEN_FILE,
+            rook_on_semi_open_file: ROOK_ON_SEMI_OPEN_FILE,
+            rook_on_seventh: ROOK_ON_SEVENTH,
+            trapped_bishop: TRAPPED_BISHOP,
+            trapped_rook: TRAPPED_ROOK,
+            term_weights: TERM_WEIGHTS,
+        }
+    }
+}
+
+impl EvalParams {
+    /// Material plus piece-square value of `piece` on `coord`, as
+    /// `(middlegame, endgame)`.
+    pub fn piece_value(&self, piece: &Piece, coord: Coord) -> (i16, i16) {
+        let (material, pst) = self.piece_terms(piece, coord);
+
+        (material.0 + pst.0, material.1 + pst.1)
+    }
+
+    /// Material and piece-square value of `piece` on `coord`, apart.
+    pub fn piece_terms(&self, piece: &Piece, coord: Coord) -> ((i16, i16), (i16, i16)) {
+        let index = piece.to_index() as usize;
+        let square = if piece.is_white() {
+            coord.to_index() as usize
+        } else {
+            coord.to_index() as usize ^ 56
+        };
+
+        (
+            (self.material[0][index], self.material[1][index]),
+            (self.pst[0][index][square], self.pst[1][index][square]),
+        )
+    }
+
+    /// Score of `board` with these parameters, positive when white is
+    /// better. Each term is scaled by its weight in `term_weights`.
+    pub fn evaluate_white(&self, board: &Board) -> i16 {
+        let trace = EvalTrace::new(board, self);
+        let mut mg = 0i32;
+        let mut eg = 0i32;
+
+        for (term, weight) in EvalTerm::ALL.iter().zip(self.term_weights) {
+            let (term_mg, term_eg) = trace.difference(*term);
+            mg += term_mg as i32 * weight as i32 / 100;
+            eg += term_eg as i32 * weight as i32 / 100;
+        }
+
+        taper(mg as i16, eg as i16, trace.phase)
+    }
+
+    /// Every parameter by the name it has in files, in file order.
+    pub fn fields_mut(&mut self) -> Vec<(String, &mut [i16])> {
+        let [material_mg, material_eg] = &mut self.material;
+        let mut fields: Vec<(String, &mut [i16])> = vec![
+            ("material_mg".to_string(), material_mg),
+            ("material_eg".to_string(), material_eg),
+        ];
+
+        let [pst_mg, pst_eg] = &mut self.pst;
+        for (phase, tables) in [("mg", pst_mg), ("eg", pst_eg)] {
+            for (name, table) in PIECE_NAMES.iter().zip(tables.iter_mut()) {
+                fields.push((format!("pst_{}_{}", phase, name), table));
+            }
+        }
+
+        fields.extend([
+            (
+                "passed_pawn".to_string(),
+                self.passed_pawn.as_flattened_mut(),
+            ),
+            (
+                "candidate_pawn".to_string(),
+                self.candidate_pawn.as_flattened_mut(),
+            ),
+            (
+                "connected_pawn".to_string(),
+                self.connected_pawn.as_flattened_mut(),
+            ),
+            ("isolated_pawn".to_string(), &mut self.isolated_pawn[..]),
+            ("doubled_pawn".to_string(), &mut self.doubled_pawn[..]),
+            ("backward_pawn".to_string(), &mut self.backward_pawn[..]),
+            ("pawn_island".to_string(), &mut self.pawn_island[..]),
+            (
+                "pawn_shield".to_string(),
+                self.pawn_shield.as_flattened_mut(),
+            ),
+            ("pawn_storm".to_string(), self.pawn_storm.as_flattened_mut()),
+            (
+                "semi_open_file_near_king".to_string(),
+                &mut self.semi_open_file_near_king[..],
+            ),
+            (
+                "open_file_near_king".to_string(),
+                &mut self.open_file_near_king[..],
+            ),
+            (
+                "king_attack_weights".to_string(),
+                &mut self.king_attack_weights[..],
+            ),
+            ("king_danger".to_string(), &mut self.king_danger[..]),
+            ("mobility".to_string(), self.mobility.as_flattened_mut()),
+            (
+                "mobility_baseline".to_string(),
+                &mut self.mobility_baseline[..],
+            ),
+            ("bishop_pair".to_string(), &mut self.bishop_pair[..]),
+            ("knight_outpost".to_string(), &mut self.knight_outpost[..]),
+            (
+                "rook_on_open_file".to_string(),
+                &mut self.rook_on_open_file[..],
+            ),
+            (
+                "rook_on_semi_open_file".to_string(),
+                &mut self.rook_on_semi_open_file[..],
+            ),
+            ("rook_on_seventh".to_string(), &mut self.rook_on_seventh[..]),
+            ("trapped_bishop".to_string(), &mut self.trapped_bishop[..]),
+            ("trapped_rook".to_string(), &mut self.trapped_rook[..]),
+            ("term_weights".to_string(), &mut self.term_weights[..]),
+        ]);
+
+        fields
+    }
+
+    /// Parses parameters written as `name = v1 v2 ...`. Values may continue
+    /// on the following lines, `#` starts a comment, and parameters left
+    /// out keep their default.
+    pub fn parse(text: &str) -> Result<EvalParams, String> {
+        let mut values: Vec<(String, Vec<i16>)> = Vec::new();
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+
+            let tokens = match line.split_once('=') {
+                Some((name, rest)) => {
+                    values.push((name.trim().to_string(), Vec::new()));
+                    rest
+                }
+                None => line,
+            };
+
+            for token in tokens.split_whitespace() {
+                let value = token
+                    .parse()
+                    .map_err(|_| format!("line {}: invalid value '{}'", number + 1, token))?;
+
+                match values.last_mut() {
+                    Some((_, current)) => current.push(value),
+                    None => return Err(format!("line {}: value before any name", number + 1)),
+                }
+            }
+        }
+
+        let mut params = EvalParams::default();
+        let mut fields = params.fields_mut();
+
+        for (name, parsed) in values {
+            let Some((_, field)) = fields.iter_mut().find(|(field, _)| *field == name) else {
+                return Err(format!("unknown parameter '{}'", name));
+            };
+
+            if parsed.len() != field.len() {
+                return Err(format!(
+                    "'{}' needs {} values, got {}",
+                    name,
+                    field.len(),
+                    parsed.len()
+                ));
+            }
+
+            field.copy_from_slice(&parsed);
+        }
+
+        drop(fields);
+
+        Ok(params)
+    }
+
+    pub fn load(path: &str) -> Result<EvalParams, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+
+        EvalParams::parse(&text).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+}
+
+impl Display for EvalParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "# Evaluation parameters, [middlegame, endgame] pairs")?;
+        writeln!(f, "# and piece-square tables from white's side, a1 first")?;
+
+        for (name, values) in self.clone().fields_mut() {
+            if values.len() <= 8 {
+                let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+                writeln!(f, "{} = {}", name, values.join(" "))?;
+                continue;
+            }
+
+            writeln!(f, "{} =", name)?;
+            for row in values.chunks(8) {
+                let row: Vec<String> = row.iter().map(|v| format!("{:>5}", v)).collect();
+                writeln!(f, "   {}", row.join(""))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_match_tables() {
+        for color in [true, false] {
+            for index in 0..6 {
+                let piece = Piece::from_index(index, color);
+
+                for i in 0..64 {
+                    let coord = Coord::from_index(i);
+
+                    assert_eq!(
+                        DEFAULT_PARAMS.piece_value(&piece, coord),
+                        piece.get_value(coord)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut params = EvalParams {
+            bishop_pair: [12, 34],
+            ..EvalParams::default()
+        };
+        params.pst[1][3][63] = -7;
+
+        assert_eq!(EvalParams::parse(&params.to_string()), Ok(params));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let partial = EvalParams::parse("# only one\nbishop_pair = 1 2\n").unwrap();
+        assert_eq!(partial.bishop_pair, [1, 2]);
+        assert_eq!(partial.knight_outpost, KNIGHT_OUTPOST);
+
+        assert!(EvalParams::parse("bishop_pair = 1").is_err());
+        assert!(EvalParams::parse("unknown = 1 2").is_err());
+        assert!(EvalParams::parse("bishop_pair = 1 x").is_err());
+    }
+}
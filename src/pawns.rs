@@ -0,0 +1,252 @@
+use crate::bitboard::BitBoard;
+use crate::board::Board;
+use crate::cache::pawn::PAWN_ATTACKS;
+use crate::coord::Coord;
+use crate::params::EvalParams;
+
+pub const PAWN_TABLE_SIZE: usize = 16384;
+
+pub const FILE_A: u64 = 0x0101_0101_0101_0101;
+
+pub fn file_mask(file: u8) -> u64 {
+    FILE_A << file
+}
+
+pub fn adjacent_files_mask(file: u8) -> u64 {
+    let mut mask = 0;
+    if file > 0 {
+        mask |= file_mask(file - 1);
+    }
+    if file < 7 {
+        mask |= file_mask(file + 1);
+    }
+    mask
+}
+
+/// All squares on ranks strictly in front of `rank`, from `color`'s side.
+pub fn ranks_ahead(rank: u8, color: bool) -> u64 {
+    if color {
+        if rank >= 7 {
+            0
+        } else {
+            !0u64 << ((rank + 1) * 8)
+        }
+    } else if rank == 0 {
+        0
+    } else {
+        !0u64 >> ((8 - rank) * 8)
+    }
+}
+
+fn relative_rank(coord: Coord, color: bool) -> usize {
+    if color {
+        coord.y as usize
+    } else {
+        7 - coord.y as usize
+    }
+}
+
+/// Pawn structure score of `color`'s pawns as `(middlegame, endgame)`.
+pub fn evaluate_pawns_side(own: u64, enemy: u64, color: bool, params: &EvalParams) -> (i16, i16) {
+    let mut mg = 0;
+    let mut eg = 0;
+
+    let mut pawns = own;
+    while pawns != 0 {
+        let index = pawns.trailing_zeros() as u8;
+        pawns &= pawns - 1;
+
+        let coord = Coord::from_index(index);
+        let rank = relative_rank(coord, color);
+        let ahead = ranks_ahead(coord.y, color);
+        let file = file_mask(coord.x);
+        let adjacent = adjacent_files_mask(coord.x);
+
+        let doubled = own & file & ahead != 0;
+        let opposed = enemy & file & ahead != 0;
+        let passed = !doubled && enemy & (file | adjacent) & ahead == 0;
+        let isolated = own & adjacent == 0;
+        let defenders = PAWN_ATTACKS[!color as usize][index as usize].get_board() & own;
+        let phalanx = own & adjacent & (0xFF << (coord.y * 8)) != 0;
+
+        if passed {
+            mg += params.passed_pawn[0][rank];
+            eg += params.passed_pawn[1][rank];
+        }
+
+        if doubled {
+            mg += params.doubled_pawn[0];
+            eg += params.doubled_pawn[1];
+        }
+
+        if isolated {
+            mg += params.isolated_pawn[0];
+            eg += params.isolated_pawn[1];
+        } else if defenders != 0 || phalanx {
+            mg += params.connected_pawn[0][rank];
+            eg += params.connected_pawn[1][rank];
+        } else {
+            // No neighbour can ever defend it, and it cannot safely advance
+            let supporters = own & adjacent & !ahead;
+            let stop = if color {
+                index + 8
+            } else {
+                index.wrapping_sub(8)
+            };
+            let stop_attacked =
+                stop < 64 && PAWN_ATTACKS[color as usize][stop as usize].get_board() & enemy != 0;
+
+            if supporters == 0 && stop_attacked {
+                mg += params.backward_pawn[0];
+                eg += params.backward_pawn[1];
+            }
+        }
+
+        // A candidate has a half-open file and at least as many friendly
+        // pawns able to support its advance as enemy pawns guarding it
+        if !passed && !opposed {
+            let sentries = (enemy & adjacent & ahead).count_ones();
+            let helpers = (own & adjacent & !ahead).count_ones();
+
+            if helpers >= sentries {
+                mg += params.candidate_pawn[0][rank];
+                eg += params.candidate_pawn[1][rank];
+            }
+        }
+    }
+
+    let islands = pawn_islands(own) as i16;
+    if islands > 1 {
+        mg += params.pawn_island[0] * (islands - 1);
+        eg += params.pawn_island[1] * (islands - 1);
+    }
+
+    (mg, eg)
+}
+
+/// Number of groups of pawns on adjacent files.
+pub fn pawn_islands(pawns: u64) -> u32 {
+    let mut files: u8 = 0;
+    for file in 0..8 {
+        if pawns & file_mask(file) != 0 {
+            files |= 1 << file;
+        }
+    }
+
+    // Count the first file of every group
+    (files & !(files << 1)).count_ones()
+}
+
+/// Pawn structure score, white minus black, as `(middlegame, endgame)`.
+pub fn evaluate_pawns(white: BitBoard, black: BitBoard, params: &EvalParams) -> (i16, i16) {
+    let (white_mg, white_eg) =
+        evaluate_pawns_side(white.get_board(), black.get_board(), true, params);
+    let (black_mg, black_eg) =
+        evaluate_pawns_side(black.get_board(), white.get_board(), false, params);
+
+    (white_mg - black_mg, white_eg - black_eg)
+}
+
+#[derive(Clone, Copy, Default)]
+struct PawnEntry {
+    key: u64,
+    mg: i16,
+    eg: i16,
+}
+
+/// Caches `evaluate_pawns` with the board's parameters by its pawn-only
+/// Zobrist key, so every board probing one table must use the same
+/// parameters. Pawn structures change rarely during a search, so most
+/// probes hit.
+pub struct PawnHashTable {
+    entries: Vec<PawnEntry>,
+}
+
+impl PawnHashTable {
+    pub fn new(size: usize) -> Self {
+        Self {
+            entries: vec![PawnEntry::default(); size.max(1)],
+        }
+    }
+
+    pub fn probe(&mut self, board: &Board) -> (i16, i16) {
+        let key = board.pawn_hash();
+        let index = (key % self.entries.len() as u64) as usize;
+        let entry = self.entries[index];
+
+        if entry.key == key && key != 0 {
+            return (entry.mg, entry.eg);
+        }
+
+        let (mg, eg) = evaluate_pawns(
+            board.get_piece_index_bitboard(0, true),
+            board.get_piece_index_bitboard(0, false),
+            board.params(),
+        );
+        self.entries[index] = PawnEntry { key, mg, eg };
+
+        (mg, eg)
+    }
+}
+
+impl Default for PawnHashTable {
+    fn default() -> Self {
+        Self::new(PAWN_TABLE_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::params::DEFAULT_PARAMS;
+
+    fn pawns(fen: &str) -> (i16, i16) {
+        let board = Board::from_fen(fen).unwrap();
+
+        evaluate_pawns(
+            board.get_piece_index_bitboard(0, true),
+            board.get_piece_index_bitboard(0, false),
+            &DEFAULT_PARAMS,
+        )
+    }
+
+    #[test]
+    fn test_symmetric_structure() {
+        assert_eq!(pawns(Board::STARTING_FEN), (0, 0));
+    }
+
+    #[test]
+    fn test_passed_pawn() {
+        let (_, eg) = pawns("4k3/8/1P6/8/8/8/p7/4K3 w");
+
+        // Both pawns are passed and isolated, black's is one rank further
+        assert!(eg < 0);
+        assert!(pawns("4k3/8/8/P7/8/8/8/4K3 w").1 > pawns("4k3/1p6/8/P7/8/8/8/4K3 w").1);
+    }
+
+    #[test]
+    fn test_doubled_and_isolated() {
+        let healthy = pawns("4k3/8/8/8/8/8/PP6/4K3 w");
+        let doubled = pawns("4k3/8/8/8/8/P7/P7/4K3 w");
+
+        assert!(doubled.0 < healthy.0);
+        assert!(doubled.1 < healthy.1);
+    }
+
+    #[test]
+    fn test_pawn_islands() {
+        assert_eq!(pawn_islands(0), 0);
+        assert_eq!(pawn_islands(file_mask(0) | file_mask(1)), 1);
+        assert_eq!(pawn_islands(file_mask(0) | file_mask(2) | file_mask(7)), 3);
+    }
+
+    #[test]
+    fn test_hash_table_matches_direct_evaluation() {
+        let fen = "4k3/pp3ppp/8/3p4/3P4/8/PP3PPP/4K3 w";
+        let board = Board::from_fen(fen).unwrap();
+        let mut table = PawnHashTable::new(64);
+
+        assert_eq!(table.probe(&board), pawns(fen));
+        assert_eq!(table.probe(&board), pawns(fen));
+    }
+}
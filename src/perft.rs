@@ -0,0 +1,73 @@
+use crate::board::Board;
+use crate::r#move::Move;
+
+/// Number of leaf nodes of the legal move tree to `depth`, the usual way to
+/// check move generation against known counts.
+pub fn perft(board: &mut Board, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let moves = board.get_all_moves();
+
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    let mut nodes = 0;
+
+    for m in moves {
+        board.make_move(m);
+        nodes += perft(board, depth - 1);
+        board.undo_move(m);
+    }
+
+    nodes
+}
+
+/// `perft` split by root move, to find which move a wrong count comes from.
+/// Empty at depth 0, where no move is played.
+pub fn divide(board: &mut Board, depth: u32) -> Vec<(Move, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+
+    board
+        .get_all_moves()
+        .into_iter()
+        .map(|m| {
+            board.make_move(m);
+            let nodes = perft(board, depth - 1);
+            board.undo_move(m);
+
+            (m, nodes)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_perft_start_position() {
+        let mut board = Board::new_game();
+
+        assert_eq!(perft(&mut board, 1), 20);
+        assert_eq!(perft(&mut board, 2), 400);
+        assert_eq!(perft(&mut board, 3), 8902);
+        assert_eq!(board.to_fen(), Board::new_game().to_fen());
+    }
+
+    #[test]
+    fn test_divide() {
+        let mut board = Board::new_game();
+        let moves = divide(&mut board, 2);
+
+        assert_eq!(moves.len(), 20);
+        assert!(moves.iter().all(|(_, nodes)| *nodes == 20));
+
+        assert_eq!(perft(&mut board, 0), 1);
+        assert!(divide(&mut board, 0).is_empty());
+    }
+}
@@ -0,0 +1,561 @@
+//! Portable Game Notation.
+//!
+//! Games are read into a tree: the main line is a list of `PgnNode`s, and
+//! each node keeps the variations that were given as alternatives to its
+//! move. Every move is resolved against the position it is played in, so a
+//! parsed game always replays on `Board`.
+
+use crate::board::Board;
+use crate::r#move::Move;
+use std::fmt::Display;
+
+/// Tags every PGN game starts with, in this order.
+const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// Move suffix annotations and the NAG each one stands for.
+const SUFFIXES: [(&str, u8); 6] = [
+    ("!!", 3),
+    ("??", 4),
+    ("!?", 5),
+    ("?!", 6),
+    ("!", 1),
+    ("?", 2),
+];
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct PgnNode {
+    pub m: Move,
+    pub san: String,
+    pub nags: Vec<u8>,
+    /// Comment before the move, only on the first move of a variation: the
+    /// main line keeps it in `PgnGame::comment`.
+    pub comment_before: Option<String>,
+    /// Comment following the move.
+    pub comment: Option<String>,
+    /// Lines played instead of this move.
+    pub variations: Vec<Vec<PgnNode>>,
+}
+
+impl PgnNode {
+    fn new(board: &Board, m: Move) -> PgnNode {
+        PgnNode {
+            m,
+            san: board.move_to_san(m),
+            nags: Vec::new(),
+            comment_before: None,
+            comment: None,
+            variations: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    /// Comment before the first move.
+    pub comment: Option<String>,
+    pub moves: Vec<PgnNode>,
+    pub result: String,
+}
+
+impl PgnGame {
+    /// An empty game with the Seven Tag Roster filled with placeholders.
+    pub fn new() -> PgnGame {
+        let mut game = PgnGame {
+            tags: Vec::new(),
+            comment: None,
+            moves: Vec::new(),
+            result: "*".to_string(),
+        };
+
+        for name in SEVEN_TAG_ROSTER {
+            game.set_tag(name, if name == "Result" { "*" } else { "?" });
+        }
+
+        game
+    }
+
+    /// A game of `moves` played from `start`. The FEN and SetUp tags are
+    /// added unless `start` is the standard starting position.
+    pub fn from_moves(start: &Board, moves: &[Move], result: &str) -> PgnGame {
+        let mut game = PgnGame::new();
+        let mut board = start.clone();
+
+        // Castling and en passant are not supported, the rest of the FEN
+        // fields only matter to other programs
+        if start.to_fen() != Board::STARTING_FEN {
+            game.set_tag("SetUp", "1");
+            game.set_tag("FEN", &format!("{} - - 0 1", start.to_fen()));
+        }
+
+        for m in moves {
+            game.moves.push(PgnNode::new(&board, *m));
+            board.make_move(*m);
+        }
+
+        game.set_result(result);
+        game
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, current)) => *current = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// Sets both the game termination marker and the Result tag.
+    pub fn set_result(&mut self, result: &str) {
+        self.result = result.to_string();
+        self.set_tag("Result", result);
+    }
+
+    /// The position the game starts from, taken from the FEN tag if any.
+    pub fn start_board(&self) -> Result<Board, String> {
+        match self.tag("FEN") {
+            Some(fen) => Board::from_fen(fen).ok_or_else(|| format!("invalid FEN tag: {}", fen)),
+            None => Ok(Board::new_game()),
+        }
+    }
+
+    pub fn mainline(&self) -> Vec<Move> {
+        self.moves.iter().map(|node| node.m).collect()
+    }
+
+    /// The position at the end of the main line.
+    pub fn board(&self) -> Result<Board, String> {
+        let mut board = self.start_board()?;
+
+        for m in self.mainline() {
+            board.make_move(m);
+        }
+
+        Ok(board)
+    }
+}
+
+impl Default for PgnGame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The PGN result of a game that ended on `board`, `*` if it is still going.
+pub fn result_of(board: &Board) -> &'static str {
+    if board.is_checkmate(board.turn) {
+        if board.turn {
+            "0-1"
+        } else {
+            "1-0"
+        }
+    } else if board.is_game_over() {
+        "1/2-1/2"
+    } else {
+        "*"
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    Open,
+    Close,
+    Result(String),
+    San(String),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            _ if c.is_whitespace() => {
+                chars.next();
+            }
+            '[' => {
+                chars.next();
+
+                // A `]` inside the quoted value does not end the tag
+                let mut tag = String::new();
+                let (mut quoted, mut escaped) = (false, false);
+                for c in chars.by_ref() {
+                    if c == ']' && !quoted {
+                        break;
+                    }
+                    if c == '"' && !escaped {
+                        quoted = !quoted;
+                    }
+                    escaped = c == '\\' && !escaped;
+                    tag.push(c);
+                }
+
+                let (name, value) = tag
+                    .trim()
+                    .split_once(char::is_whitespace)
+                    .ok_or_else(|| format!("invalid tag: [{}]", tag))?;
+                let value = value.trim().trim_matches('"').replace("\\\"", "\"");
+
+                tokens.push(Token::Tag(name.to_string(), value));
+            }
+            '{' => {
+                chars.next();
+                let comment: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                tokens.push(Token::Comment(comment.trim().to_string()));
+            }
+            ';' => {
+                let comment: String = chars.by_ref().take_while(|c| *c != '\n').collect();
+                tokens.push(Token::Comment(comment[1..].trim().to_string()));
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "[]{}();".contains(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+
+                tokens.extend(word_tokens(&word)?);
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Tokens of a movetext word: a NAG, a result, or a move possibly glued
+/// to its number and followed by suffix annotations.
+fn word_tokens(word: &str) -> Result<Vec<Token>, String> {
+    if let Some(nag) = word.strip_prefix('$') {
+        let nag = nag.parse().map_err(|_| format!("invalid NAG: {}", word))?;
+        return Ok(vec![Token::Nag(nag)]);
+    }
+
+    if RESULTS.contains(&word) {
+        return Ok(vec![Token::Result(word.to_string())]);
+    }
+
+    // Move numbers like `12.` or `12...`, possibly glued to the move
+    let word = match word.rfind('.') {
+        Some(dot) if word[..dot].chars().all(|c| c.is_ascii_digit() || c == '.') => {
+            &word[dot + 1..]
+        }
+        _ => word,
+    };
+    if word.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    for (suffix, nag) in SUFFIXES {
+        if let Some(san) = word.strip_suffix(suffix) {
+            return Ok(vec![Token::San(san.to_string()), Token::Nag(nag)]);
+        }
+    }
+
+    Ok(vec![Token::San(word.to_string())])
+}
+
+fn append_comment(target: &mut Option<String>, comment: &str) {
+    *target = Some(match target.take() {
+        Some(previous) => format!("{} {}", previous, comment),
+        None => comment.to_string(),
+    });
+}
+
+/// Parses a line of moves played from `board` up to the end of the current
+/// variation, leaving `board` at the end of the line. Comments before the
+/// first move go to `leading`.
+fn parse_line(
+    tokens: &[Token],
+    position: &mut usize,
+    board: &mut Board,
+    leading: &mut Option<String>,
+) -> Result<Vec<PgnNode>, String> {
+    let mut line: Vec<PgnNode> = Vec::new();
+
+    while let Some(token) = tokens.get(*position) {
+        match token {
+            Token::San(san) => {
+                let m = board.parse_san(san)?;
+                line.push(PgnNode::new(board, m));
+                board.make_move(m);
+            }
+            Token::Nag(nag) => match line.last_mut() {
+                Some(node) => node.nags.push(*nag),
+                None => return Err(format!("NAG ${} before any move", nag)),
+            },
+            Token::Comment(comment) => match line.last_mut() {
+                Some(node) => append_comment(&mut node.comment, comment),
+                None => append_comment(leading, comment),
+            },
+            Token::Open => {
+                let Some(last) = line.last_mut() else {
+                    return Err("variation before any move".to_string());
+                };
+
+                let mut variation_board = board.clone();
+                variation_board.undo_move(last.m);
+
+                *position += 1;
+                let mut variation_comment = None;
+                let mut variation = parse_line(
+                    tokens,
+                    position,
+                    &mut variation_board,
+                    &mut variation_comment,
+                )?;
+                if tokens.get(*position) != Some(&Token::Close) {
+                    return Err("unterminated variation".to_string());
+                }
+
+                if let Some(comment) = variation_comment {
+                    match variation.first_mut() {
+                        Some(first) => first.comment_before = Some(comment),
+                        None => return Err(format!("comment {{{}}} without a move", comment)),
+                    }
+                }
+
+                line.last_mut().unwrap().variations.push(variation);
+            }
+            Token::Close | Token::Result(_) | Token::Tag(..) => return Ok(line),
+        }
+
+        *position += 1;
+    }
+
+    Ok(line)
+}
+
+/// Parses every game of a PGN file.
+pub fn parse_pgn(text: &str) -> Result<Vec<PgnGame>, String> {
+    let tokens = tokenize(text)?;
+    let mut games = Vec::new();
+    let mut position = 0;
+
+    while position < tokens.len() {
+        let mut game = PgnGame::new();
+
+        while let Some(Token::Tag(name, value)) = tokens.get(position) {
+            game.set_tag(name, value);
+            position += 1;
+        }
+
+        let mut board = game.start_board()?;
+        game.moves = parse_line(&tokens, &mut position, &mut board, &mut game.comment)?;
+
+        match tokens.get(position) {
+            Some(Token::Result(result)) => {
+                game.set_result(result);
+                position += 1;
+            }
+            Some(Token::Close) => return Err("unmatched ')'".to_string()),
+            _ => {
+                let result = game.tag("Result").unwrap_or("*").to_string();
+                game.result = result;
+            }
+        }
+
+        games.push(game);
+    }
+
+    Ok(games)
+}
+
+pub fn load_pgn(path: &str) -> Result<Vec<PgnGame>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+
+    parse_pgn(&text).map_err(|e| format!("{}: {}", path, e))
+}
+
+/// Movetext tokens of `line`, `ply` being the ply of its first move
+/// counted from 0 for white's first move.
+fn line_tokens(line: &[PgnNode], mut ply: usize, tokens: &mut Vec<String>) {
+    // Black's moves need their number after anything interrupting the line
+    let mut numbered = false;
+
+    for node in line {
+        if let Some(comment) = &node.comment_before {
+            tokens.push(format!("{{{}}}", comment));
+        }
+
+        if ply.is_multiple_of(2) {
+            tokens.push(format!("{}.", ply / 2 + 1));
+        } else if !numbered {
+            tokens.push(format!("{}...", ply / 2 + 1));
+        }
+
+        tokens.push(node.san.clone());
+        tokens.extend(node.nags.iter().map(|nag| format!("${}", nag)));
+        numbered = true;
+
+        if let Some(comment) = &node.comment {
+            tokens.push(format!("{{{}}}", comment));
+            numbered = false;
+        }
+
+        for variation in &node.variations {
+            let mut variation_tokens = Vec::new();
+            line_tokens(variation, ply, &mut variation_tokens);
+
+            tokens.push(format!("({})", variation_tokens.join(" ")));
+            numbered = false;
+        }
+
+        ply += 1;
+    }
+}
+
+impl Display for PgnGame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let roster = SEVEN_TAG_ROSTER
+            .iter()
+            .filter_map(|name| self.tags.iter().find(|(tag, _)| tag == name));
+        let others = self
+            .tags
+            .iter()
+            .filter(|(tag, _)| !SEVEN_TAG_ROSTER.contains(&tag.as_str()));
+
+        for (name, value) in roster.chain(others) {
+            writeln!(f, "[{} \"{}\"]", name, value.replace('"', "\\\""))?;
+        }
+        writeln!(f)?;
+
+        let mut tokens = Vec::new();
+        if let Some(comment) = &self.comment {
+            tokens.push(format!("{{{}}}", comment));
+        }
+
+        let first_ply = self
+            .start_board()
+            .map(|board| !board.turn as usize)
+            .unwrap_or(0);
+        line_tokens(&self.moves, first_ply, &mut tokens);
+        tokens.push(self.result.clone());
+
+        // Lines of at most 80 characters, as the standard asks
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > 80 {
+                writeln!(f, "{}", line)?;
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+
+        writeln!(f, "{}", line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GAME: &str = r#"[Event "Test"]
+[Site "?"]
+[Date "2024.01.01"]
+[Round "1"]
+[White "A"]
+[Black "B"]
+[Result "1-0"]
+
+{Opening} 1. e4 e5 2. Nf3 $1 (2. Qh5 Nc6 3. Bc4 {Scholar's} Nf6?? 4. Qxf7#) 2... Nc6
+3. Bb5!? ; Ruy Lopez
+a6 1-0
+"#;
+
+    #[test]
+    fn test_parse_game() {
+        let games = parse_pgn(GAME).unwrap();
+        assert_eq!(games.len(), 1);
+
+        let game = &games[0];
+        assert_eq!(game.tag("Event"), Some("Test"));
+        assert_eq!(game.result, "1-0");
+        assert_eq!(game.comment.as_deref(), Some("Opening"));
+        assert_eq!(game.moves.len(), 6);
+        assert_eq!(game.moves[2].nags, vec![1]);
+        assert_eq!(game.moves[4].nags, vec![5]);
+        assert_eq!(game.moves[4].comment.as_deref(), Some("Ruy Lopez"));
+
+        let variation = &game.moves[2].variations[0];
+        assert_eq!(variation.len(), 5);
+        assert_eq!(variation[3].nags, vec![4]);
+        assert_eq!(variation[4].san, "Qxf7#");
+
+        assert_eq!(
+            game.board().unwrap().to_fen(),
+            "r1bqkbnr/1ppp1ppp/p1n5/1B2p3/4P3/5N2/PPPP1PPP/RNBQK2R w"
+        );
+    }
+
+    #[test]
+    fn test_write_and_reparse() {
+        let game = &parse_pgn(GAME).unwrap()[0];
+        let text = game.to_string();
+        let movetext = text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        assert!(text.lines().all(|line| line.len() <= 80));
+        assert!(movetext
+            .contains("2. Nf3 $1 (2. Qh5 Nc6 3. Bc4 {Scholar's} 3... Nf6 $4 4. Qxf7#) 2... Nc6"));
+        assert_eq!(&parse_pgn(&text).unwrap()[0], game);
+    }
+
+    #[test]
+    fn test_variation_comment_and_quoted_tag() {
+        let text = "[Event \"Open [Rapid]\"]\n\n{Start} 1. e4 ( {Or} 1. d4 ) 1... e5 *";
+        let game = &parse_pgn(text).unwrap()[0];
+
+        assert_eq!(game.tag("Event"), Some("Open [Rapid]"));
+        assert_eq!(game.comment.as_deref(), Some("Start"));
+        assert_eq!(
+            game.moves[0].variations[0][0].comment_before.as_deref(),
+            Some("Or")
+        );
+        assert!(game.to_string().contains("1. e4 ({Or} 1. d4) 1... e5 *"));
+        assert_eq!(&parse_pgn(&game.to_string()).unwrap()[0], game);
+
+        assert!(parse_pgn("1. e4 ( {Nothing} ) e5").is_err());
+    }
+
+    #[test]
+    fn test_from_moves() {
+        let start = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w").unwrap();
+        let game = PgnGame::from_moves(&start, &[Move::from_str("a1a8")], "1-0");
+        let text = game.to_string();
+
+        assert!(text.contains("[FEN \"6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\"]"));
+        assert!(text.ends_with("1. Ra8# 1-0\n"));
+        assert_eq!(result_of(&game.board().unwrap()), "1-0");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(parse_pgn("1. e4 e4").is_err());
+        assert!(parse_pgn("1. e4 (1. d4").is_err());
+        assert!(parse_pgn("1. e4 )").is_err());
+    }
+}
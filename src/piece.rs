@@ -1,6 +1,8 @@
 use crate::coord::Coord;
 use crate::evaluate::{
-    BISHOP_SCORE, KING_SCORE, KNIGHT_SCORE, PAWN_SCORE, QUEEN_SCORE, ROOK_SCORE,
+    BISHOP_SCORE, BISHOP_SCORE_EG, KING_SCORE, KING_SCORE_EG, KNIGHT_SCORE, KNIGHT_SCORE_EG,
+    MATERIAL_EG, MATERIAL_MG, PAWN_SCORE, PAWN_SCORE_EG, PHASE_WEIGHTS, QUEEN_SCORE,
+    QUEEN_SCORE_EG, ROOK_SCORE, ROOK_SCORE_EG,
 };
 
 #[derive(PartialEq, Clone, Copy)]
@@ -26,7 +28,7 @@ impl PieceEnum {
         }
     }
 
-    pub fn to_index(&self) -> u8 {
+    pub fn to_index(self) -> u8 {
         match self {
             PieceEnum::Pawn => 0,
             PieceEnum::Knight => 1,
@@ -49,7 +51,7 @@ impl PieceEnum {
         }
     }
 
-    pub fn to_char(&self) -> char {
+    pub fn to_char(self) -> char {
         match self {
             PieceEnum::Pawn => 'P',
             PieceEnum::Knight => 'N',
@@ -86,7 +88,7 @@ impl Piece {
         Some(Piece::new(piece, color))
     }
 
-    pub fn to_char(&self) -> char {
+    pub fn to_char(self) -> char {
         if self.color {
             self.piece.to_char().to_ascii_uppercase()
         } else {
@@ -94,7 +96,7 @@ impl Piece {
         }
     }
 
-    pub fn to_index(&self) -> u8 {
+    pub fn to_index(self) -> u8 {
         match self.piece {
             PieceEnum::Pawn => 0,
             PieceEnum::Knight => 1,
@@ -105,27 +107,46 @@ impl Piece {
         }
     }
 
+    /// Middlegame piece-square bonus of this piece on `coord`.
     pub fn get_score(&self, coord: Coord) -> i16 {
-        match self.piece {
-            PieceEnum::Pawn => {
-                PAWN_SCORE[if self.color { 1 } else { 0 }][coord.to_index() as usize]
-            }
-            PieceEnum::Knight => {
-                KNIGHT_SCORE[if self.color { 1 } else { 0 }][coord.to_index() as usize]
-            }
-            PieceEnum::Bishop => {
-                BISHOP_SCORE[if self.color { 1 } else { 0 }][coord.to_index() as usize]
-            }
-            PieceEnum::Rook => {
-                ROOK_SCORE[if self.color { 1 } else { 0 }][coord.to_index() as usize]
-            }
-            PieceEnum::Queen => {
-                QUEEN_SCORE[if self.color { 1 } else { 0 }][coord.to_index() as usize]
-            }
-            PieceEnum::King => {
-                KING_SCORE[if self.color { 1 } else { 0 }][coord.to_index() as usize]
-            }
-        }
+        let table = match self.piece {
+            PieceEnum::Pawn => &PAWN_SCORE,
+            PieceEnum::Knight => &KNIGHT_SCORE,
+            PieceEnum::Bishop => &BISHOP_SCORE,
+            PieceEnum::Rook => &ROOK_SCORE,
+            PieceEnum::Queen => &QUEEN_SCORE,
+            PieceEnum::King => &KING_SCORE,
+        };
+
+        table[self.color as usize][coord.to_index() as usize]
+    }
+
+    /// Endgame piece-square bonus of this piece on `coord`.
+    pub fn get_eg_score(&self, coord: Coord) -> i16 {
+        let table = match self.piece {
+            PieceEnum::Pawn => &PAWN_SCORE_EG,
+            PieceEnum::Knight => &KNIGHT_SCORE_EG,
+            PieceEnum::Bishop => &BISHOP_SCORE_EG,
+            PieceEnum::Rook => &ROOK_SCORE_EG,
+            PieceEnum::Queen => &QUEEN_SCORE_EG,
+            PieceEnum::King => &KING_SCORE_EG,
+        };
+
+        table[self.color as usize][coord.to_index() as usize]
+    }
+
+    /// Material plus piece-square value, as a `(middlegame, endgame)` pair.
+    pub fn get_value(&self, coord: Coord) -> (i16, i16) {
+        let index = self.to_index() as usize;
+
+        (
+            MATERIAL_MG[index] + self.get_score(coord),
+            MATERIAL_EG[index] + self.get_eg_score(coord),
+        )
+    }
+
+    pub fn get_phase(&self) -> i16 {
+        PHASE_WEIGHTS[self.to_index() as usize]
     }
 
     pub fn is_white(&self) -> bool {
@@ -0,0 +1,231 @@
+use crate::board::Board;
+use crate::coord::Coord;
+use crate::piece::PieceEnum;
+use crate::r#move::Move;
+
+/// Standard Algebraic Notation, as used in PGN files and by most humans.
+impl Board {
+    /// `m` in SAN for the side to move, e.g. `Nbd7`, `exd5`, `O-O` or
+    /// `Qh4#`. Moves with no piece on their origin square fall back to
+    /// coordinate notation. Pawns are never promoted by `make_move`, so
+    /// neither is there a promotion suffix.
+    pub fn move_to_san(&self, m: Move) -> String {
+        let piece = match self.get_piece(m.from) {
+            Some(piece) => piece,
+            None => return m.to_str(),
+        };
+
+        let mut san = String::new();
+
+        if piece.piece == PieceEnum::King && m.from.x.abs_diff(m.to.x) == 2 {
+            san.push_str(if m.to.x > m.from.x { "O-O" } else { "O-O-O" });
+        } else {
+            let capture = m.capture || self.has_piece(m.to);
+
+            if piece.piece == PieceEnum::Pawn {
+                if capture {
+                    san.push(file_char(m.from.x));
+                }
+            } else {
+                san.push(piece.piece.to_char());
+                san.push_str(&self.disambiguation(m, piece.piece));
+            }
+
+            if capture {
+                san.push('x');
+            }
+
+            san.push_str(&m.to.to_str());
+        }
+
+        let mut after = self.clone();
+        after.make_move(m);
+
+        if after.is_checkmate(after.turn) {
+            san.push('#');
+        } else if after.is_check(after.turn) {
+            san.push('+');
+        }
+
+        san
+    }
+
+    /// The origin file, rank or square needed to tell `m` apart from other
+    /// legal moves of the same kind of piece to the same square.
+    fn disambiguation(&self, m: Move, piece: PieceEnum) -> String {
+        let others: Vec<Coord> = self
+            .get_all_moves()
+            .into_iter()
+            .filter(|other| {
+                other.to == m.to
+                    && other.from != m.from
+                    && self.get_piece(other.from).map(|p| p.piece) == Some(piece)
+            })
+            .map(|other| other.from)
+            .collect();
+
+        if others.is_empty() {
+            String::new()
+        } else if others.iter().all(|from| from.x != m.from.x) {
+            file_char(m.from.x).to_string()
+        } else if others.iter().all(|from| from.y != m.from.y) {
+            rank_char(m.from.y).to_string()
+        } else {
+            m.from.to_str()
+        }
+    }
+
+    /// Resolves a SAN move against the legal moves of the side to move.
+    /// Check, mate and annotation suffixes are optional, and so is the `x`
+    /// of captures. Promotions such as `e8=Q` are rejected, as the board
+    /// cannot play them.
+    pub fn parse_san(&self, san: &str) -> Result<Move, String> {
+        let text = san.trim().trim_end_matches(['+', '#', '!', '?']);
+        let moves = self.get_all_moves();
+
+        if matches!(text, "O-O" | "O-O-O" | "0-0" | "0-0-0") {
+            let king_side = text.len() == 3;
+
+            return moves
+                .into_iter()
+                .find(|m| {
+                    self.get_piece(m.from).map(|p| p.piece) == Some(PieceEnum::King)
+                        && m.from.x.abs_diff(m.to.x) == 2
+                        && (m.to.x > m.from.x) == king_side
+                })
+                .ok_or_else(|| format!("illegal move: {}", san));
+        }
+
+        if text.contains('=') {
+            return Err(format!("unsupported promotion: {}", san));
+        }
+
+        let mut chars: Vec<char> = text.chars().filter(|c| *c != 'x').collect();
+
+        let piece = match chars.first().and_then(|c| PieceEnum::from_char(*c)) {
+            Some(piece) if piece != PieceEnum::Pawn => {
+                chars.remove(0);
+                piece
+            }
+            _ => PieceEnum::Pawn,
+        };
+
+        if chars.len() < 2 {
+            return Err(format!("invalid move: {}", san));
+        }
+
+        let to = match chars.split_off(chars.len() - 2).as_slice() {
+            [file @ 'a'..='h', rank @ '1'..='8'] => {
+                Coord::new(*file as u8 - b'a', *rank as u8 - b'1')
+            }
+            _ => return Err(format!("invalid square in {}", san)),
+        };
+
+        let mut file = None;
+        let mut rank = None;
+        for c in chars {
+            match c {
+                'a'..='h' => file = Some(c as u8 - b'a'),
+                '1'..='8' => rank = Some(c as u8 - b'1'),
+                _ => return Err(format!("invalid move: {}", san)),
+            }
+        }
+
+        let candidates: Vec<Move> = moves
+            .into_iter()
+            .filter(|m| {
+                m.to == to
+                    && self.get_piece(m.from).map(|p| p.piece) == Some(piece)
+                    && file.is_none_or(|file| m.from.x == file)
+                    && rank.is_none_or(|rank| m.from.y == rank)
+            })
+            .collect();
+
+        match candidates.as_slice() {
+            [] => Err(format!("illegal move: {}", san)),
+            [m] => Ok(*m),
+            _ => Err(format!("ambiguous move: {}", san)),
+        }
+    }
+}
+
+fn file_char(file: u8) -> char {
+    (b'a' + file) as char
+}
+
+fn rank_char(rank: u8) -> char {
+    (b'1' + rank) as char
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_move_to_san() {
+        let board = Board::new_game();
+
+        assert_eq!(board.move_to_san(Move::from_str("e2e4")), "e4");
+        assert_eq!(board.move_to_san(Move::from_str("g1f3")), "Nf3");
+
+        let board = Board::from_fen("6k1/5ppp/8/3p4/4P3/8/8/R5K1 w").unwrap();
+        assert_eq!(board.move_to_san(Move::from_str("e4d5")), "exd5");
+        assert_eq!(board.move_to_san(Move::from_str("a1a8")), "Ra8#");
+        assert_eq!(board.move_to_san(Move::from_str("a1a7")), "Ra7");
+    }
+
+    #[test]
+    fn test_disambiguation() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/4K3/R6R w").unwrap();
+        assert_eq!(board.move_to_san(Move::from_str("a1d1")), "Rad1");
+
+        let board = Board::from_fen("4k3/8/8/8/R7/8/8/R3K3 w").unwrap();
+        assert_eq!(board.move_to_san(Move::from_str("a1a2")), "R1a2");
+
+        let board = Board::from_fen("4k3/8/8/8/Q1Q5/8/Q7/4K3 w").unwrap();
+        assert_eq!(board.move_to_san(Move::from_str("a4b3")), "Qa4b3");
+    }
+
+    #[test]
+    fn test_parse_san() {
+        let board = Board::new_game();
+
+        assert_eq!(board.parse_san("e4"), Ok(Move::from_str("e2e4")));
+        assert_eq!(board.parse_san("Nf3+"), Ok(Move::from_str("g1f3")));
+        assert!(board.parse_san("e5").unwrap_err().starts_with("illegal"));
+        assert!(board.parse_san("O-O").is_err());
+        assert!(board.parse_san("Zz9").is_err());
+
+        let board = Board::from_fen("4k3/8/8/8/8/8/4K3/R6R w").unwrap();
+        assert!(board.parse_san("Rd1").unwrap_err().starts_with("ambiguous"));
+        assert_eq!(board.parse_san("Rad1"), Ok(Move::from_str("a1d1")));
+        assert_eq!(board.parse_san("Rhf1"), Ok(Move::from_str("h1f1")));
+    }
+
+    #[test]
+    fn test_promotions_are_rejected() {
+        let board = Board::new_game();
+        assert!(board.parse_san("e4=Q").is_err());
+        assert!(board.parse_san("Nf3=Q").is_err());
+
+        // The pawn move itself is legal, and stays a pawn move as in UCI
+        let board = Board::from_fen("7k/P7/8/8/8/8/8/K7 w").unwrap();
+        assert!(board.parse_san("a8=Q").is_err());
+        assert!(board.parse_san("a8=N").is_err());
+
+        let m = board.parse_san("a8").unwrap();
+        assert_eq!(board.parse_uci("a7a8q"), Ok(m));
+        assert!(board.get_all_moves().contains(&m));
+        assert_eq!(board.move_to_san(m), "a8");
+    }
+
+    #[test]
+    fn test_san_round_trip() {
+        let board =
+            Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w").unwrap();
+
+        for m in board.get_all_moves() {
+            assert_eq!(board.parse_san(&board.move_to_san(m)), Ok(m));
+        }
+    }
+}
@@ -0,0 +1,869 @@
+use crate::board::Board;
+use crate::evaluator::{ClassicEvaluator, Evaluator};
+use crate::limits::SearchLimits;
+use crate::r#move::Move;
+use crate::tt::{TTEntry, TranspositionTable};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+pub use crate::tt::Bound;
+
+pub const INFINITY: i16 = 10000;
+pub const MATE_SCORE: i16 = 9999;
+pub const MAX_PLY: u32 = 64;
+
+const SINGULAR_MIN_DEPTH: u32 = 6;
+const ASPIRATION_MIN_DEPTH: u32 = 3;
+const ASPIRATION_WINDOW: i16 = 25;
+
+/// Nodes a thread counts on its own before adding them to the shared
+/// total, which node limits and reports are checked against.
+pub const NODE_FLUSH_INTERVAL: u64 = 2048;
+
+/// Which search extensions are enabled, and how many plies a single line may
+/// be extended by in total.
+#[derive(Clone, Copy)]
+pub struct Extensions {
+    pub check: bool,
+    pub singular: bool,
+    pub recapture: bool,
+    pub passed_pawn: bool,
+    pub max_extensions: u32,
+}
+
+impl Default for Extensions {
+    fn default() -> Self {
+        Self {
+            check: true,
+            singular: true,
+            recapture: false,
+            passed_pawn: false,
+            max_extensions: 16,
+        }
+    }
+}
+
+/// Formats a score as the UCI `cp <x>` or `mate <n>` (moves, not plies).
+pub fn format_score(score: i16) -> String {
+    if score.abs() >= MATE_SCORE - MAX_PLY as i16 {
+        let plies = (MATE_SCORE - score.abs()) as i32;
+        let moves = (plies + 1) / 2;
+        format!("mate {}", if score > 0 { moves } else { -moves })
+    } else {
+        format!("cp {}", score)
+    }
+}
+
+/// Mate scores count plies from the root. The transposition table stores
+/// them counted from the node instead, so that they stay right when the
+/// position is reached at another ply.
+fn score_to_tt(score: i16, ply: u32) -> i16 {
+    if score >= MATE_SCORE - MAX_PLY as i16 {
+        score + ply as i16
+    } else if score <= -(MATE_SCORE - MAX_PLY as i16) {
+        score - ply as i16
+    } else {
+        score
+    }
+}
+
+/// Inverse of `score_to_tt`.
+fn score_from_tt(score: i16, ply: u32) -> i16 {
+    if score >= MATE_SCORE - MAX_PLY as i16 {
+        score - ply as i16
+    } else if score <= -(MATE_SCORE - MAX_PLY as i16) {
+        score + ply as i16
+    } else {
+        score
+    }
+}
+
+/// Progress of a search, reported after each iteration and whenever the
+/// root score falls outside the aspiration window.
+#[derive(Clone, Debug)]
+pub struct SearchInfo {
+    pub depth: u32,
+    pub seldepth: u32,
+    /// Score for the side to move; a lower or upper bound unless `Exact`.
+    pub score: i16,
+    pub bound: Bound,
+    pub nodes: u64,
+    pub time: Duration,
+    /// Rank of the line, 1 for the best one.
+    pub multipv: usize,
+    pub pv: Vec<Move>,
+}
+
+/// One line of a MultiPV search: a root move and the play expected after it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PvLine {
+    /// Score for the side to move.
+    pub score: i16,
+    pub pv: Vec<Move>,
+}
+
+/// The outcome of a search, from the deepest completed iteration.
+#[derive(Clone, Debug)]
+pub struct SearchResult {
+    pub best_move: Move,
+    /// The expected reply, second move of the principal variation.
+    pub ponder_move: Option<Move>,
+    /// Score for the side to move.
+    pub score: i16,
+    pub pv: Vec<Move>,
+    /// With MultiPV, the best lines found, best first. The first one is the
+    /// principal variation.
+    pub lines: Vec<PvLine>,
+    pub depth: u32,
+    pub seldepth: u32,
+    pub nodes: u64,
+    pub time: Duration,
+}
+
+/// Receives search progress. Called on the thread running the main search
+/// thread, which is not necessarily the caller's.
+pub trait InfoCallback: Send {
+    fn info(&mut self, info: &SearchInfo);
+}
+
+impl<F: FnMut(&SearchInfo) + Send> InfoCallback for F {
+    fn info(&mut self, info: &SearchInfo) {
+        self(info)
+    }
+}
+
+/// Prints UCI `info` lines.
+pub struct UciInfo;
+
+impl InfoCallback for UciInfo {
+    fn info(&mut self, info: &SearchInfo) {
+        let bound = match info.bound {
+            Bound::Exact => "",
+            Bound::Lower => " lowerbound",
+            Bound::Upper => " upperbound",
+        };
+        let pv: Vec<String> = info.pv.iter().map(|m| m.to_str()).collect();
+
+        println!(
+            "info depth {} seldepth {} multipv {} score {}{} nodes {} time {} pv {}",
+            info.depth,
+            info.seldepth,
+            info.multipv,
+            format_score(info.score),
+            bound,
+            info.nodes,
+            info.time.as_millis(),
+            pv.join(" ")
+        );
+    }
+}
+
+/// State shared between all threads of one search.
+pub struct SharedSearch<'a> {
+    pub tt: &'a TranspositionTable,
+    pub stop: &'a AtomicBool,
+    /// Nodes summed over all threads, each adding its own every
+    /// `NODE_FLUSH_INTERVAL` nodes and after each iteration.
+    pub nodes: &'a AtomicU64,
+}
+
+/// One thread of a (Lazy SMP) search. Each thread owns its board copy, move
+/// ordering tables and evaluator, and only shares the transposition table.
+pub struct SearchThread<'a> {
+    pub board: Board,
+    pub extensions: Extensions,
+    pub nodes: u64,
+    pub seldepth: u32,
+    /// Depth and best lines of the last completed iteration.
+    pub completed_depth: u32,
+    pub lines: Vec<PvLine>,
+    shared: SharedSearch<'a>,
+    history: Box<[[i32; 64]; 64]>,
+    evaluator: Box<dyn Evaluator>,
+    killers: [[Move; 2]; MAX_PLY as usize],
+    // Triangular table: the line found from each ply, kept while searching
+    pv_table: Box<[[Move; MAX_PLY as usize + 1]; MAX_PLY as usize + 1]>,
+    pv_length: [usize; MAX_PLY as usize + 1],
+    is_main: bool,
+    callback: Option<&'a mut dyn InfoCallback>,
+    node_limit: Option<u64>,
+    mate: Option<u32>,
+    searchmoves: Vec<Move>,
+    multipv: usize,
+    // Root moves of the lines already found in the current iteration
+    root_excluded: Vec<Move>,
+    start: Instant,
+}
+
+impl<'a> SearchThread<'a> {
+    pub fn new(board: Board, extensions: Extensions, shared: SharedSearch<'a>) -> Self {
+        Self {
+            board,
+            extensions,
+            nodes: 0,
+            seldepth: 0,
+            completed_depth: 0,
+            lines: Vec::new(),
+            shared,
+            history: Box::new([[0; 64]; 64]),
+            evaluator: Box::new(ClassicEvaluator::new()),
+            killers: [[Move::null(); 2]; MAX_PLY as usize],
+            pv_table: Box::new([[Move::null(); MAX_PLY as usize + 1]; MAX_PLY as usize + 1]),
+            pv_length: [0; MAX_PLY as usize + 1],
+            is_main: true,
+            callback: None,
+            node_limit: None,
+            mate: None,
+            searchmoves: Vec::new(),
+            multipv: 1,
+            root_excluded: Vec::new(),
+            start: Instant::now(),
+        }
+    }
+
+    /// Marks this thread as a helper: it never reports and its result is
+    /// discarded, it only fills the shared transposition table.
+    pub fn helper(mut self) -> Self {
+        self.is_main = false;
+        self
+    }
+
+    pub fn with_evaluator(mut self, evaluator: Box<dyn Evaluator>) -> Self {
+        self.evaluator = evaluator;
+        self
+    }
+
+    /// Reports progress to `callback`, on the main thread only.
+    pub fn with_callback(mut self, callback: &'a mut dyn InfoCallback) -> Self {
+        self.callback = Some(callback);
+        self
+    }
+
+    /// Searches the `multipv` best root moves instead of only the best.
+    pub fn with_multipv(mut self, multipv: usize) -> Self {
+        self.multipv = multipv.max(1);
+        self
+    }
+
+    /// Applies the limits checked by the search itself: nodes, mate and
+    /// root moves. Depth is given to `iterate`, and time is up to the
+    /// caller, who sets the stop flag.
+    pub fn with_limits(mut self, limits: &SearchLimits) -> Self {
+        self.node_limit = limits.max_nodes();
+        self.mate = limits
+            .mate
+            .filter(|_| !limits.infinite)
+            .map(|mate| mate.min(MAX_PLY));
+        self.searchmoves = limits.searchmoves.clone();
+        self
+    }
+
+    /// Whether to stop searching. The first iteration always completes, so
+    /// that even a search stopped at once has a searched move to play.
+    fn stopped(&self) -> bool {
+        self.completed_depth > 0 && self.shared.stop.load(Ordering::Relaxed)
+    }
+
+    /// Nodes searched so far by every thread, as far as this thread knows.
+    fn total_nodes(&self) -> u64 {
+        self.shared.nodes.load(Ordering::Relaxed) + self.nodes
+    }
+
+    /// Counts a node at `ply`, stopping the search once the node limit is
+    /// reached.
+    fn count_node(&mut self, ply: u32) {
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply);
+
+        if self.nodes >= NODE_FLUSH_INTERVAL {
+            self.flush_nodes();
+        }
+
+        if let Some(limit) = self.node_limit {
+            if self.total_nodes() >= limit {
+                self.shared.stop.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// How many plies to extend `m` by, given the move that preceded it.
+    /// Must be called after `m` has been made on the board.
+    fn extension(&self, m: Move, captured: bool, previous: Option<Move>) -> u32 {
+        if self.extensions.check && self.board.is_check(self.board.turn) {
+            return 1;
+        }
+
+        if self.extensions.recapture && captured {
+            if let Some(previous) = previous {
+                if previous.capture && previous.to == m.to {
+                    return 1;
+                }
+            }
+        }
+
+        if self.extensions.passed_pawn {
+            if let Some(piece) = self.board.get_piece(m.to) {
+                let seventh = if piece.is_white() { 6 } else { 1 };
+                if piece.is_pawn()
+                    && m.to.y == seventh
+                    && self.board.is_passed_pawn(m.to, piece.is_white())
+                {
+                    return 1;
+                }
+            }
+        }
+
+        0
+    }
+
+    /// Returns true if `m`, the transposition table move, is the only move
+    /// that does not fail low against a margin below the stored score.
+    fn is_singular(
+        &mut self,
+        m: Move,
+        entry: &TTEntry,
+        depth: u32,
+        ply: u32,
+        extended: u32,
+    ) -> bool {
+        if !self.extensions.singular
+            || depth < SINGULAR_MIN_DEPTH
+            || entry.best_move != m
+            || entry.depth + 3 < depth
+            || entry.bound == Bound::Upper
+            || entry.score.abs() >= MATE_SCORE - MAX_PLY as i16
+        {
+            return false;
+        }
+
+        let singular_beta = entry.score - 2 * depth as i16;
+        let score = self.negamax(
+            (depth - 1) / 2,
+            ply,
+            singular_beta - 1,
+            singular_beta,
+            extended,
+            Some(m),
+        );
+
+        score < singular_beta
+    }
+
+    /// Sorts `moves` best first: the hash move, winning and equal captures by
+    /// MVV-LVA, killers, quiet moves by history score, then losing captures.
+    fn order_moves(&self, moves: &mut [Move], hash_move: Move, ply: u32) {
+        let killers = self.killers[ply as usize];
+
+        moves.sort_by_cached_key(|m| {
+            if *m == hash_move {
+                return i32::MIN;
+            }
+
+            if let Some(victim) = self.board.get_piece(m.to) {
+                let attacker = self.board.get_piece(m.from).unwrap();
+                let mvv_lva = victim.to_index() as i32 * 10 - attacker.to_index() as i32;
+
+                return if self.board.see_ge(*m, 0) {
+                    -1_000_000 - mvv_lva
+                } else {
+                    1_000_000 - mvv_lva
+                };
+            }
+
+            if *m == killers[0] {
+                -900_000
+            } else if *m == killers[1] {
+                -800_000
+            } else {
+                -self.history[m.from.to_index() as usize][m.to.to_index() as usize]
+            }
+        });
+    }
+
+    fn store_cutoff(&mut self, m: Move, depth: u32, ply: u32) {
+        if self.board.has_piece(m.to) {
+            return;
+        }
+
+        let killers = &mut self.killers[ply as usize];
+        if killers[0] != m {
+            killers[1] = killers[0];
+            killers[0] = m;
+        }
+
+        let history = &mut self.history[m.from.to_index() as usize][m.to.to_index() as usize];
+        *history = (*history + (depth * depth) as i32).min(800_000);
+    }
+
+    fn negamax(
+        &mut self,
+        depth: u32,
+        ply: u32,
+        alpha: i16,
+        beta: i16,
+        extended: u32,
+        excluded: Option<Move>,
+    ) -> i16 {
+        self.pv_length[ply as usize] = 0;
+
+        if self.stopped() {
+            return 0;
+        }
+
+        if depth == 0 || ply >= MAX_PLY {
+            return self.quiescence(ply, alpha, beta);
+        }
+
+        self.count_node(ply);
+
+        let mut alpha = alpha;
+        let original_alpha = alpha;
+        let mut max = -INFINITY;
+        let mut best_move = Move::null();
+        let mut searched = 0;
+
+        let hash = self.board.hash();
+
+        // Searches excluding a move must not share entries with full searches
+        let entry = if excluded.is_none() {
+            self.shared.tt.get(hash)
+        } else {
+            None
+        };
+
+        if let Some(entry) = entry {
+            if entry.depth >= depth {
+                let score = score_from_tt(entry.score, ply);
+                match entry.bound {
+                    Bound::Exact => return score,
+                    Bound::Lower if score >= beta => return score,
+                    Bound::Upper if score <= alpha => return score,
+                    _ => {}
+                }
+            }
+        }
+
+        let previous = self.board.last_move().map(|last| last.m);
+
+        let mut moves = self.board.get_all_moves();
+        let hash_move = entry.map_or(Move::null(), |entry| entry.best_move);
+        self.order_moves(&mut moves, hash_move, ply);
+
+        for m in moves {
+            if Some(m) == excluded {
+                continue;
+            }
+
+            let singular = match entry {
+                Some(entry) => self.is_singular(m, &entry, depth, ply, extended),
+                None => false,
+            };
+
+            let captured = self.board.has_piece(m.to);
+            self.board.make_move(m);
+
+            let mut extension = if singular {
+                1
+            } else {
+                self.extension(m, captured, previous)
+            };
+            if extended + extension > self.extensions.max_extensions {
+                extension = 0;
+            }
+
+            let score = -self.negamax(
+                depth - 1 + extension,
+                ply + 1,
+                -beta,
+                -alpha,
+                extended + extension,
+                None,
+            );
+
+            self.board.undo_move(m);
+
+            if self.stopped() {
+                return 0;
+            }
+
+            searched += 1;
+
+            if score > max {
+                max = score;
+                best_move = m;
+            }
+
+            if score > alpha && excluded.is_none() {
+                self.update_pv(m, ply);
+            }
+
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                self.store_cutoff(m, depth, ply);
+                break;
+            }
+        }
+
+        if searched == 0 {
+            if excluded.is_some() {
+                return alpha;
+            }
+
+            return if self.board.is_check(self.board.turn) {
+                -(MATE_SCORE - ply as i16)
+            } else {
+                0
+            };
+        }
+
+        if excluded.is_none() {
+            let bound = if max <= original_alpha {
+                Bound::Upper
+            } else if max >= beta {
+                Bound::Lower
+            } else {
+                Bound::Exact
+            };
+
+            self.shared.tt.set(
+                hash,
+                TTEntry {
+                    depth,
+                    score: score_to_tt(max, ply),
+                    bound,
+                    best_move,
+                },
+            );
+        }
+
+        max
+    }
+
+    /// Captures-only search at the leaves, so that the static evaluation is
+    /// not taken in the middle of an exchange. Captures that lose material
+    /// by static exchange evaluation are pruned.
+    fn quiescence(&mut self, ply: u32, alpha: i16, beta: i16) -> i16 {
+        self.pv_length[ply as usize] = 0;
+        self.count_node(ply);
+
+        let stand_pat = self.evaluator.evaluate(&self.board);
+        if stand_pat >= beta || ply >= MAX_PLY {
+            return stand_pat;
+        }
+
+        let mut alpha = alpha.max(stand_pat);
+
+        let mut captures: Vec<Move> = self
+            .board
+            .get_all_moves()
+            .into_iter()
+            .filter(|m| m.capture && self.board.see_ge(*m, 0))
+            .collect();
+        captures.sort_by_key(|m| -self.board.see(*m));
+
+        for m in captures {
+            self.board.make_move(m);
+            let score = -self.quiescence(ply + 1, -beta, -alpha);
+            self.board.undo_move(m);
+
+            if self.stopped() {
+                return 0;
+            }
+
+            if score > alpha {
+                alpha = score;
+                self.update_pv(m, ply);
+
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+
+        alpha
+    }
+
+    /// Searches the root position inside the `(alpha, beta)` window, trying
+    /// `first` before the other moves. Returns the best move and its score.
+    pub fn search_root(&mut self, depth: u32, alpha: i16, beta: i16, first: Move) -> (Move, i16) {
+        let mut alpha = alpha;
+        let mut best_move_value = -INFINITY;
+        let mut best_move = Move::null();
+
+        let previous = self.board.last_move().map(|last| last.m);
+        self.pv_length[0] = 0;
+
+        let mut moves = self.board.get_all_moves();
+        if !self.searchmoves.is_empty() {
+            moves.retain(|m| self.searchmoves.contains(m));
+        }
+        moves.retain(|m| !self.root_excluded.contains(m));
+        self.order_moves(&mut moves, first, 0);
+
+        for m in moves {
+            let captured = self.board.has_piece(m.to);
+            self.board.make_move(m); // Changing the actual board state
+
+            let extension = self
+                .extension(m, captured, previous)
+                .min(self.extensions.max_extensions);
+
+            let move_value =
+                -self.negamax(depth - 1 + extension, 1, -beta, -alpha, extension, None);
+
+            self.board.undo_move(m);
+
+            if self.stopped() {
+                break;
+            }
+
+            if move_value > best_move_value {
+                best_move_value = move_value;
+                best_move = m;
+            }
+
+            if move_value > alpha {
+                self.update_pv(m, 0);
+            }
+
+            alpha = alpha.max(move_value);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        (best_move, best_move_value)
+    }
+
+    /// Iterative deepening up to `depth`, finding as many lines as set by
+    /// `with_multipv`. Returns the best move and its score.
+    pub fn iterate(&mut self, depth: u32) -> (Move, i16) {
+        let mut best: (Move, i16) = (Move::null(), 0);
+
+        let root_moves = self
+            .board
+            .get_all_moves()
+            .into_iter()
+            .filter(|m| self.searchmoves.is_empty() || self.searchmoves.contains(m))
+            .count();
+        let line_count = self.multipv.min(root_moves).max(1);
+
+        for current_depth in 1..=depth.max(1) {
+            let mut lines = Vec::with_capacity(line_count);
+
+            // Each line is the best move once the moves of the lines above
+            // are left out
+            for index in 0..line_count {
+                let previous = self.lines.get(index).map(|line| (line.pv[0], line.score));
+                let (m, score) = self.aspiration(current_depth, previous, index + 1);
+
+                if self.stopped() {
+                    break;
+                }
+
+                self.root_excluded.push(m);
+                lines.push(PvLine {
+                    score,
+                    pv: self.root_pv(m),
+                });
+            }
+
+            self.root_excluded.clear();
+            self.flush_nodes();
+
+            if self.stopped() {
+                break;
+            }
+
+            lines.sort_by_key(|line| std::cmp::Reverse(line.score));
+            best = (lines[0].pv[0], lines[0].score);
+
+            self.completed_depth = current_depth;
+            for (index, line) in lines.iter().enumerate() {
+                self.report(current_depth, line.score, Bound::Exact, &line.pv, index + 1);
+            }
+            self.lines = lines;
+
+            if let Some(mate) = self.mate {
+                if best.1 >= MATE_SCORE - (2 * mate as i16 - 1) {
+                    break;
+                }
+            }
+        }
+
+        best
+    }
+
+    /// Searches the root to `depth`. From `ASPIRATION_MIN_DEPTH` on, the
+    /// window is centered on the `previous` score of this line, widened on
+    /// the failing side until the score falls inside it.
+    fn aspiration(
+        &mut self,
+        depth: u32,
+        previous: Option<(Move, i16)>,
+        multipv: usize,
+    ) -> (Move, i16) {
+        let (first, previous_score) = previous.unwrap_or((Move::null(), 0));
+
+        let mut delta = ASPIRATION_WINDOW;
+        let (mut alpha, mut beta) = if depth < ASPIRATION_MIN_DEPTH || previous.is_none() {
+            (-INFINITY, INFINITY)
+        } else {
+            (
+                previous_score.saturating_sub(delta).max(-INFINITY),
+                previous_score.saturating_add(delta).min(INFINITY),
+            )
+        };
+
+        loop {
+            let (m, score) = self.search_root(depth, alpha, beta, first);
+
+            if self.stopped() {
+                return (m, score);
+            }
+
+            if score <= alpha && alpha > -INFINITY {
+                let pv = self.root_pv(m);
+                self.report(depth, score, Bound::Upper, &pv, multipv);
+                beta = ((alpha as i32 + beta as i32) / 2) as i16;
+                alpha = score.saturating_sub(delta).max(-INFINITY);
+            } else if score >= beta && beta < INFINITY {
+                let pv = self.root_pv(m);
+                self.report(depth, score, Bound::Lower, &pv, multipv);
+                beta = score.saturating_add(delta).min(INFINITY);
+            } else {
+                return (m, score);
+            }
+
+            delta = delta.saturating_mul(2);
+        }
+    }
+
+    /// Makes `m` followed by the line of the next ply the line of `ply`.
+    fn update_pv(&mut self, m: Move, ply: u32) {
+        let ply = ply as usize;
+        let child_length = if ply < MAX_PLY as usize {
+            self.pv_length[ply + 1]
+        } else {
+            0
+        };
+
+        self.pv_table[ply][0] = m;
+        for i in 0..child_length.min(MAX_PLY as usize - ply) {
+            self.pv_table[ply][i + 1] = self.pv_table[ply + 1][i];
+        }
+        self.pv_length[ply] = 1 + child_length.min(MAX_PLY as usize - ply);
+    }
+
+    /// The line found at the root if it starts with `m`, or just `m`.
+    fn root_pv(&self, m: Move) -> Vec<Move> {
+        if self.pv_length[0] > 0 && self.pv_table[0][0] == m {
+            self.pv_table[0][..self.pv_length[0]].to_vec()
+        } else {
+            vec![m]
+        }
+    }
+
+    fn flush_nodes(&mut self) {
+        self.shared.nodes.fetch_add(self.nodes, Ordering::Relaxed);
+        self.nodes = 0;
+    }
+
+    /// Passes progress to the callback, if this is the main thread.
+    fn report(&mut self, depth: u32, score: i16, bound: Bound, pv: &[Move], multipv: usize) {
+        if !self.is_main {
+            return;
+        }
+
+        let info = SearchInfo {
+            depth,
+            seldepth: self.seldepth,
+            score,
+            bound,
+            nodes: self.total_nodes(),
+            time: self.start.elapsed(),
+            multipv,
+            pv: pv.to_vec(),
+        };
+
+        if let Some(callback) = self.callback.as_mut() {
+            callback.info(&info);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(fen: &str, depth: u32, full_window: bool) -> (Move, i16) {
+        let tt = TranspositionTable::new(1);
+        let stop = AtomicBool::new(false);
+        let nodes = AtomicU64::new(0);
+        let shared = SharedSearch {
+            tt: &tt,
+            stop: &stop,
+            nodes: &nodes,
+        };
+
+        let board = Board::from_fen(fen).unwrap();
+        let mut thread = SearchThread::new(board, Extensions::default(), shared);
+        if full_window {
+            thread.search_root(depth, -INFINITY, INFINITY, Move::null())
+        } else {
+            thread.iterate(depth)
+        }
+    }
+
+    #[test]
+    fn test_aspiration_matches_full_window() {
+        let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w";
+
+        assert_eq!(search(fen, 3, false).1, search(fen, 3, true).1);
+    }
+
+    #[test]
+    fn test_quiescence_sees_recapture() {
+        // At depth 1 the queen would grab the pawn without the recapture
+        let (m, score) = search("4k3/8/4p3/3p4/8/8/8/3QK3 w", 1, false);
+
+        assert_ne!(m.to_str(), "d1d5");
+        assert!(score > 0);
+    }
+
+    #[test]
+    fn test_black_takes_free_queen() {
+        let (m, score) = search("4k3/8/8/3r4/8/3Q4/8/4K3 b", 2, false);
+
+        assert_eq!(m.to_str(), "d5d3");
+        assert!(score > 0);
+    }
+
+    #[test]
+    fn test_tt_mate_scores() {
+        // Mate in 2 found at ply 3 is mate in 1 from a node at ply 1
+        assert_eq!(score_to_tt(MATE_SCORE - 3, 1), MATE_SCORE - 2);
+        assert_eq!(score_from_tt(MATE_SCORE - 2, 1), MATE_SCORE - 3);
+        assert_eq!(score_to_tt(-(MATE_SCORE - 4), 2), -(MATE_SCORE - 2));
+        assert_eq!(
+            score_from_tt(score_to_tt(-(MATE_SCORE - 4), 2), 2),
+            -(MATE_SCORE - 4)
+        );
+        assert_eq!(score_to_tt(150, 5), 150);
+
+        // 1. Kf7 Kh7 2. Rh1#, reported as mate in 2 at every depth
+        for depth in 3..6 {
+            assert_eq!(
+                search("7k/8/5K2/8/8/8/8/R7 w", depth, false).1,
+                MATE_SCORE - 3
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_score() {
+        assert_eq!(format_score(35), "cp 35");
+        assert_eq!(format_score(MATE_SCORE - 1), "mate 1");
+        assert_eq!(format_score(-(MATE_SCORE - 2)), "mate -1");
+    }
+}
@@ -0,0 +1,179 @@
+use crate::board::Board;
+use crate::coord::Coord;
+use crate::evaluate::{taper, MAX_PHASE};
+use crate::king_safety::evaluate_king_safety_side;
+use crate::mobility::evaluate_mobility_side;
+use crate::params::EvalParams;
+use crate::pawns::evaluate_pawns_side;
+use std::fmt::Display;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum EvalTerm {
+    Material,
+    PieceSquare,
+    Pawns,
+    KingSafety,
+    Mobility,
+}
+
+impl EvalTerm {
+    pub const ALL: [EvalTerm; 5] = [
+        EvalTerm::Material,
+        EvalTerm::PieceSquare,
+        EvalTerm::Pawns,
+        EvalTerm::KingSafety,
+        EvalTerm::Mobility,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EvalTerm::Material => "Material",
+            EvalTerm::PieceSquare => "Piece-square",
+            EvalTerm::Pawns => "Pawns",
+            EvalTerm::KingSafety => "King safety",
+            EvalTerm::Mobility => "Mobility",
+        }
+    }
+}
+
+/// Breakdown of `Board::evaluate_white` into its terms, each given for both
+/// sides as a `(middlegame, endgame)` pair before tapering. Built from any
+/// `EvalParams`, the board's own score uses the defaults.
+#[derive(Clone, Copy)]
+pub struct EvalTrace {
+    scores: [[(i16, i16); 2]; 5],
+    pub phase: i16,
+}
+
+impl EvalTrace {
+    pub fn new(board: &Board, params: &EvalParams) -> EvalTrace {
+        let mut scores = [[(0, 0); 2]; 5];
+
+        for i in 0..64 {
+            let coord = Coord::from_index(i);
+
+            if let Some(piece) = board.get_piece(coord) {
+                let color = piece.is_white() as usize;
+                let (material, pst) = params.piece_terms(&piece, coord);
+
+                scores[0][color].0 += material.0;
+                scores[0][color].1 += material.1;
+                scores[1][color].0 += pst.0;
+                scores[1][color].1 += pst.1;
+            }
+        }
+
+        for color in [true, false] {
+            let own = board.get_piece_index_bitboard(0, color).get_board();
+            let enemy = board.get_piece_index_bitboard(0, !color).get_board();
+
+            scores[2][color as usize] = evaluate_pawns_side(own, enemy, color, params);
+            scores[3][color as usize] = evaluate_king_safety_side(board, color, params);
+            scores[4][color as usize] = evaluate_mobility_side(board, color, params);
+        }
+
+        EvalTrace {
+            scores,
+            phase: board.game_phase(),
+        }
+    }
+
+    fn index(term: EvalTerm) -> usize {
+        EvalTerm::ALL.iter().position(|t| *t == term).unwrap()
+    }
+
+    /// Score of `term` for `color` alone, as `(middlegame, endgame)`.
+    pub fn get(&self, term: EvalTerm, color: bool) -> (i16, i16) {
+        self.scores[EvalTrace::index(term)][color as usize]
+    }
+
+    /// Score of `term`, white minus black, as `(middlegame, endgame)`.
+    pub fn difference(&self, term: EvalTerm) -> (i16, i16) {
+        let (white_mg, white_eg) = self.get(term, true);
+        let (black_mg, black_eg) = self.get(term, false);
+
+        (white_mg - black_mg, white_eg - black_eg)
+    }
+
+    /// Tapered total, positive when white is better. Always equal to
+    /// `Board::evaluate_white` with the default parameters.
+    pub fn total(&self) -> i16 {
+        let (mg, eg) = EvalTerm::ALL
+            .iter()
+            .map(|term| self.difference(*term))
+            .fold((0, 0), |(mg, eg), (term_mg, term_eg)| {
+                (mg + term_mg, eg + term_eg)
+            });
+
+        taper(mg, eg, self.phase)
+    }
+}
+
+impl Display for EvalTrace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{:<14}|{:^15}|{:^15}|{:^15}",
+            "Term", "White", "Black", "Total"
+        )?;
+        writeln!(
+            f,
+            "{:<14}|{:>7}{:>7} |{:>7}{:>7} |{:>7}{:>7} ",
+            "", "MG", "EG", "MG", "EG", "MG", "EG"
+        )?;
+        writeln!(f, "{}", "-".repeat(14 + 16 * 3))?;
+
+        for term in EvalTerm::ALL {
+            let white = self.get(term, true);
+            let black = self.get(term, false);
+            let total = self.difference(term);
+
+            writeln!(
+                f,
+                "{:<14}|{:>7}{:>7} |{:>7}{:>7} |{:>7}{:>7} ",
+                term.name(),
+                white.0,
+                white.1,
+                black.0,
+                black.1,
+                total.0,
+                total.1
+            )?;
+        }
+
+        writeln!(f, "{}", "-".repeat(14 + 16 * 3))?;
+        writeln!(f, "Phase: {}/{}", self.phase.min(MAX_PHASE), MAX_PHASE)?;
+        write!(f, "Total (white side): {}", self.total())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluate::{MATERIAL_EG, MATERIAL_MG};
+
+    #[test]
+    fn test_trace_matches_evaluation() {
+        for fen in [
+            Board::STARTING_FEN,
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b",
+            "6k1/5ppp/7Q/6N1/8/8/5PPP/6K1 w",
+            "8/5k2/3p4/1p1P4/1P6/5K2/8/8 w",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+
+            assert_eq!(board.trace().total(), board.evaluate_white());
+        }
+    }
+
+    #[test]
+    fn test_material_per_side() {
+        let trace = Board::from_fen("3qk3/8/8/8/8/8/8/4K3 w").unwrap().trace();
+
+        assert_eq!(trace.get(EvalTerm::Material, true), (0, 0));
+        assert_eq!(
+            trace.get(EvalTerm::Material, false),
+            (MATERIAL_MG[4], MATERIAL_EG[4])
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use crate::coord::Coord;
+use crate::r#move::Move;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+pub const DEFAULT_TT_SIZE_MB: usize = 16;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Clone, Copy)]
+pub struct TTEntry {
+    pub depth: u32,
+    pub score: i16,
+    pub bound: Bound,
+    pub best_move: Move,
+}
+
+impl TTEntry {
+    fn pack(&self) -> u64 {
+        let bound = match self.bound {
+            Bound::Exact => 0,
+            Bound::Lower => 1,
+            Bound::Upper => 2,
+        };
+
+        (self.score as u16 as u64)
+            | ((self.depth.min(255) as u64) << 16)
+            | (bound << 24)
+            | ((self.best_move.from.to_index() as u64) << 26)
+            | ((self.best_move.to.to_index() as u64) << 32)
+            | ((self.best_move.capture as u64) << 38)
+            | ((self.best_move.promotion as u64) << 39)
+    }
+
+    fn unpack(data: u64) -> TTEntry {
+        let bound = match (data >> 24) & 0b11 {
+            0 => Bound::Exact,
+            1 => Bound::Lower,
+            _ => Bound::Upper,
+        };
+
+        TTEntry {
+            depth: ((data >> 16) & 0xFF) as u32,
+            score: data as u16 as i16,
+            bound,
+            best_move: Move::new(
+                Coord::from_index(((data >> 26) & 0x3F) as u8),
+                Coord::from_index(((data >> 32) & 0x3F) as u8),
+                (data >> 38) & 1 == 1,
+                (data >> 39) & 1 == 1,
+            ),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Slot {
+    // Stored as `hash ^ data`, so a slot torn by concurrent writers fails
+    // the key check instead of returning another position's data
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+/// Fixed-size transposition table shared by all search threads without
+/// locking. Always-replace scheme, indexed by the Zobrist key.
+pub struct TranspositionTable {
+    slots: Vec<Slot>,
+}
+
+impl TranspositionTable {
+    pub fn new(size_mb: usize) -> Self {
+        let count = (size_mb * 1024 * 1024 / std::mem::size_of::<Slot>()).max(1);
+
+        Self {
+            slots: (0..count).map(|_| Slot::default()).collect(),
+        }
+    }
+
+    fn slot(&self, hash: u64) -> &Slot {
+        &self.slots[(hash % self.slots.len() as u64) as usize]
+    }
+
+    pub fn get(&self, hash: u64) -> Option<TTEntry> {
+        let slot = self.slot(hash);
+        let key = slot.key.load(Ordering::Relaxed);
+        let data = slot.data.load(Ordering::Relaxed);
+
+        if data != 0 && key ^ data == hash {
+            Some(TTEntry::unpack(data))
+        } else {
+            None
+        }
+    }
+
+    pub fn set(&self, hash: u64, entry: TTEntry) {
+        let slot = self.slot(hash);
+        let data = entry.pack();
+
+        slot.key.store(hash ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+
+    pub fn clear(&self) {
+        for slot in &self.slots {
+            slot.key.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_and_probe() {
+        let tt = TranspositionTable::new(1);
+        let entry = TTEntry {
+            depth: 7,
+            score: -321,
+            bound: Bound::Lower,
+            best_move: Move::from_str("e2e4"),
+        };
+
+        tt.set(0xDEAD_BEEF, entry);
+
+        let probed = tt.get(0xDEAD_BEEF).unwrap();
+        assert_eq!(probed.depth, 7);
+        assert_eq!(probed.score, -321);
+        assert_eq!(probed.bound, Bound::Lower);
+        assert_eq!(probed.best_move, entry.best_move);
+        assert!(tt.get(0xDEAD_BEEF + 1).is_none());
+    }
+}
@@ -0,0 +1,343 @@
+use crate::board::Board;
+use crate::params::EvalParams;
+use crate::r#move::Move;
+use rayon::prelude::*;
+
+/// Captures deeper than this are not resolved, it only guards against
+/// pathological exchange sequences.
+const MAX_QUIESCENCE_PLY: u32 = 16;
+
+/// A quiet position with the result of the game it was taken from, from
+/// white's side: 1 for a white win, 0.5 for a draw, 0 for a loss.
+pub struct TuningPosition {
+    pub board: Board,
+    pub result: f64,
+}
+
+/// Reads the game result out of a dataset line: `1-0`, `0-1` or `1/2-1/2`,
+/// possibly quoted or bracketed as in EPD `c9` opcodes, a bracketed number
+/// such as `[0.5]`, or the last field of the `datagen` format. Bare numbers
+/// are not results, they are the move counters of six-field FENs.
+pub fn parse_result(line: &str) -> Option<f64> {
+    if let Some((_, result)) = line.rsplit_once('|') {
+        return result.trim().parse().ok();
+    }
+
+    for token in line.split_whitespace().skip(2) {
+        let token = token.trim_end_matches(';');
+
+        match token.trim_matches(|c| "\"[]".contains(c)) {
+            "1-0" => return Some(1.0),
+            "0-1" => return Some(0.0),
+            "1/2-1/2" => return Some(0.5),
+            _ => {}
+        }
+
+        let bracketed = token.strip_prefix('[').and_then(|t| t.strip_suffix(']'));
+        if let Some(Ok(result)) = bracketed.map(str::parse::<f64>) {
+            if (0.0..=1.0).contains(&result) {
+                return Some(result);
+            }
+        }
+    }
+
+    None
+}
+
+/// Captures-only search that returns the score of `board` for the side to
+/// move, filling `pv` with the captures leading to the quiet position the
+/// score comes from.
+pub fn quiesce(board: &mut Board, mut alpha: i16, beta: i16, ply: u32, pv: &mut Vec<Move>) -> i16 {
+    pv.clear();
+
+    let stand_pat = board.evaluate_relative();
+    if stand_pat >= beta || ply >= MAX_QUIESCENCE_PLY {
+        return stand_pat;
+    }
+    alpha = alpha.max(stand_pat);
+
+    let mut captures: Vec<Move> = board
+        .get_all_moves()
+        .into_iter()
+        .filter(|m| m.capture && board.see_ge(*m, 0))
+        .collect();
+    captures.sort_by_key(|m| -board.see(*m));
+
+    let mut child_pv = Vec::new();
+
+    for m in captures {
+        board.make_move(m);
+        let score = -quiesce(board, -beta, -alpha, ply + 1, &mut child_pv);
+        board.undo_move(m);
+
+        if score > alpha {
+            alpha = score;
+            pv.clear();
+            pv.push(m);
+            pv.extend_from_slice(&child_pv);
+
+            if score >= beta {
+                break;
+            }
+        }
+    }
+
+    alpha
+}
+
+/// Plays out the captures of the quiescence search, so that the static
+/// evaluation of the returned board is the quiescence score.
+pub fn resolve(board: &Board) -> Board {
+    let mut board = board.clone();
+    let mut pv = Vec::new();
+
+    quiesce(&mut board, -i16::MAX, i16::MAX, 0, &mut pv);
+
+    for m in pv {
+        board.make_move(m);
+    }
+
+    // Drop the move history, only the position matters from here on
+    Board::from_fen(&board.to_fen()).unwrap()
+}
+
+/// Expected score of white for a white-relative evaluation `score`.
+pub fn sigmoid(score: i16, k: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * score as f64 / 400.0))
+}
+
+/// Parses a dataset of one position per line, a FEN followed by the game
+/// result as accepted by `parse_result`. Positions in check, finished
+/// games and lines without a result are skipped, the rest are resolved
+/// with `resolve`.
+pub fn parse_positions(text: &str) -> Vec<TuningPosition> {
+    let lines: Vec<&str> = text.lines().collect();
+
+    lines
+        .par_iter()
+        .filter_map(|line| {
+            let result = parse_result(line)?;
+            let board = Board::from_fen(line)?;
+
+            if board.is_check(board.turn) || board.is_game_over() {
+                return None;
+            }
+
+            Some(TuningPosition {
+                board: resolve(&board),
+                result,
+            })
+        })
+        .collect()
+}
+
+pub fn load_positions(path: &str) -> Result<Vec<TuningPosition>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+
+    Ok(parse_positions(&text))
+}
+
+/// Texel tuning: a local search over every parameter, one step at a time,
+/// minimizing the squared difference between game results and the
+/// sigmoid of the evaluation.
+pub struct Tuner {
+    pub positions: Vec<TuningPosition>,
+    pub params: EvalParams,
+    pub k: f64,
+}
+
+impl Tuner {
+    pub fn new(positions: Vec<TuningPosition>, params: EvalParams) -> Tuner {
+        Tuner {
+            positions,
+            params,
+            k: 1.0,
+        }
+    }
+
+    /// Mean squared error of `params` over the positions.
+    pub fn error(&self, params: &EvalParams) -> f64 {
+        if self.positions.is_empty() {
+            return 0.0;
+        }
+
+        let total: f64 = self
+            .positions
+            .par_iter()
+            .map(|position| {
+                let score = params.evaluate_white(&position.board);
+                (position.result - sigmoid(score, self.k)).powi(2)
+            })
+            .sum();
+
+        total / self.positions.len() as f64
+    }
+
+    /// Picks the sigmoid scaling that best fits the current parameters, so
+    /// that tuning changes the evaluation and not just its scale.
+    pub fn find_k(&mut self) -> f64 {
+        let mut best = (self.k, self.error(&self.params));
+        let mut step = 0.5;
+
+        for _ in 0..4 {
+            let center = best.0;
+
+            for i in -5..=5 {
+                self.k = (center + i as f64 * step).max(0.01);
+                let error = self.error(&self.params);
+
+                if error < best.1 {
+                    best = (self.k, error);
+                }
+            }
+
+            step /= 10.0;
+        }
+
+        self.k = best.0;
+        self.k
+    }
+
+    /// Parameters the search may change, as `(field, value)` indices into
+    /// `EvalParams::fields_mut`. Values that can never matter, like the
+    /// king's material or pawns on the back ranks, are left out, and so are
+    /// the term weights and the mobility baseline, which only rescale other
+    /// parameters.
+    fn tunable(&mut self) -> Vec<(usize, usize)> {
+        let mut indices = Vec::new();
+
+        for (field, (name, values)) in self.params.fields_mut().iter().enumerate() {
+            for value in 0..values.len() {
+                let skip = match name.as_str() {
+                    "term_weights" | "mobility_baseline" => true,
+                    "material_mg" | "material_eg" => value == 5,
+                    "pst_mg_pawn" | "pst_eg_pawn" => !(8..56).contains(&value),
+                    _ => false,
+                };
+
+                if !skip {
+                    indices.push((field, value));
+                }
+            }
+        }
+
+        indices
+    }
+
+    fn adjust(&mut self, (field, value): (usize, usize), delta: i16) {
+        self.params.fields_mut()[field].1[value] += delta;
+    }
+
+    /// One pass over every tunable parameter, keeping each step up or down
+    /// that lowers the error. Returns the error after the pass.
+    pub fn pass(&mut self) -> f64 {
+        let mut best = self.error(&self.params);
+
+        for index in self.tunable() {
+            self.adjust(index, 1);
+            let error = self.error(&self.params);
+            if error < best {
+                best = error;
+                continue;
+            }
+
+            self.adjust(index, -2);
+            let error = self.error(&self.params);
+            if error < best {
+                best = error;
+                continue;
+            }
+
+            self.adjust(index, 1);
+        }
+
+        best
+    }
+
+    /// Runs up to `passes` passes, stopping early once a pass no longer
+    /// improves, and saves the parameters to `output` after each one so an
+    /// interrupted run keeps its progress.
+    pub fn run(&mut self, passes: u32, output: &str) -> Result<(), String> {
+        let k = self.find_k();
+        let mut error = self.error(&self.params);
+
+        println!(
+            "Tuning on {} positions, K = {:.3}, error = {:.6}",
+            self.positions.len(),
+            k,
+            error
+        );
+
+        for pass in 1..=passes {
+            let new_error = self.pass();
+
+            self.params
+                .save(output)
+                .map_err(|e| format!("{}: {}", output, e))?;
+            println!("Pass {}: error = {:.6}", pass, new_error);
+
+            if new_error >= error {
+                break;
+            }
+            error = new_error;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_result() {
+        assert_eq!(parse_result("4k3/8/8/8/8/8/8/4K3 w 1-0"), Some(1.0));
+        assert_eq!(parse_result("4k3/8/8/8/8/8/8/4K3 b c9 \"0-1\";"), Some(0.0));
+        assert_eq!(parse_result("4k3/8/8/8/8/8/8/4K3 w [0.5]"), Some(0.5));
+        assert_eq!(parse_result("4k3/8/8/8/8/8/8/4K3 w | 1 | 0.0"), Some(0.0));
+        assert_eq!(parse_result("4k3/8/8/8/8/8/8/4K3 w"), None);
+
+        // The move counters of a six-field FEN are not results
+        assert_eq!(
+            parse_result("4k3/8/8/8/8/8/8/4K3 w - - 0 1 [1.0]"),
+            Some(1.0)
+        );
+        assert_eq!(
+            parse_result("4k3/8/8/8/8/8/8/4K3 b - - 1 1 \"1/2-1/2\";"),
+            Some(0.5)
+        );
+        assert_eq!(parse_result("4k3/8/8/8/8/8/8/4K3 w - - 0 1"), None);
+    }
+
+    #[test]
+    fn test_resolve_plays_out_captures() {
+        // The hanging queen is taken before the position is evaluated, and
+        // with white to move the white score is the quiescence score
+        let board = Board::from_fen("4k3/8/8/3q4/8/3R4/8/4K3 w").unwrap();
+        let quiet = resolve(&board);
+
+        assert!(quiet.get_piece_index_bitboard(4, false).get_board() == 0);
+        assert_eq!(quiet.evaluate_white(), {
+            let mut board = board.clone();
+            quiesce(&mut board, -i16::MAX, i16::MAX, 0, &mut Vec::new())
+        });
+    }
+
+    #[test]
+    fn test_pass_does_not_increase_error() {
+        let positions = parse_positions(
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b 1/2-1/2\n\
+             4k3/8/8/8/8/8/4P3/3QK3 w 1-0\n\
+             3qk3/4p3/8/8/8/8/8/4K3 b 0-1\n",
+        );
+        let mut tuner = Tuner::new(positions, EvalParams::default());
+        tuner.find_k();
+
+        let before = tuner.error(&tuner.params);
+        let after = tuner.pass();
+
+        assert!(after <= before);
+        assert_eq!(after, tuner.error(&tuner.params));
+    }
+}
@@ -0,0 +1,53 @@
+use crate::coord::Coord;
+use crate::piece::Piece;
+use lazy_static::lazy_static;
+
+pub struct Zobrist {
+    pieces: [[[u64; 64]; 6]; 2],
+    black_to_move: u64,
+}
+
+lazy_static! {
+    pub static ref ZOBRIST: Zobrist = Zobrist::new();
+}
+
+impl Zobrist {
+    fn new() -> Zobrist {
+        // Fixed seed so hashes are identical between runs
+        let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        let mut pieces = [[[0; 64]; 6]; 2];
+        for color in pieces.iter_mut() {
+            for piece in color.iter_mut() {
+                for square in piece.iter_mut() {
+                    *square = next();
+                }
+            }
+        }
+
+        Zobrist {
+            pieces,
+            black_to_move: next(),
+        }
+    }
+
+    #[inline(always)]
+    pub fn piece(&self, piece: Piece, coord: Coord) -> u64 {
+        self.pieces[piece.is_white() as usize][piece.to_index() as usize][coord.to_index() as usize]
+    }
+
+    #[inline(always)]
+    pub fn turn(&self, turn: bool) -> u64 {
+        if turn {
+            0
+        } else {
+            self.black_to_move
+        }
+    }
+}